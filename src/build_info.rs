@@ -0,0 +1,17 @@
+// how the binary was built, for --version --verbose, the features decide what it
+// needs from the system it runs on
+
+/// the TLS library postgres connections use
+pub const TLS: &str = if cfg!(feature = "rustls") { "rustls" } else { "none" };
+
+/// how sqlite gets into the binary, sqlx 0.8.0 bundles it even without bundled-sqlite
+pub const SQLITE: &str = if cfg!(feature = "bundled-sqlite") { "bundled" } else { "bundled by sqlx" };
+
+/// the lines --version --verbose adds after the version
+pub fn lines() -> Vec<String> {
+    vec![
+        format!("tls: {TLS}"),
+        format!("sqlite: {SQLITE}"),
+        format!("target: {}-{}", std::env::consts::ARCH, std::env::consts::OS),
+    ]
+}
@@ -0,0 +1,187 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+// how old the cached status may be before prompt --cached asks the database, unless the config says otherwise
+pub const DEFAULT_MAX_AGE_SECS: u64 = 60;
+// the longest prompt_cache_secs, a year of 366 days
+pub const MAX_AGE_SECS: u64 = 366 * 24 * 60 * 60;
+
+// what prompt shows of one database, as it was at `updated_at`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Status {
+    pub pending: i64,
+    pub top: Option<String>,
+    pub updated_at: DateTime<Utc>,
+}
+
+// the status of one database in the cache file, shared by every database used
+#[derive(Debug, Clone, PartialEq)]
+pub struct StatusCache {
+    pub path: PathBuf,
+    /// config::fingerprint of the database with the table, so that profiles and tenants don't mix
+    pub key: String,
+}
+
+/// `db_test/status.json` in $XDG_CACHE_HOME or ~/.cache
+pub fn default_path() -> Option<PathBuf> {
+    let base = match std::env::var_os("XDG_CACHE_HOME") {
+        Some(dir) => PathBuf::from(dir),
+        None => Path::new(&std::env::var_os("HOME")?).join(".cache"),
+    };
+    Some(base.join("db_test").join("status.json"))
+}
+
+impl StatusCache {
+    /// the status of the database unless it is older than `max_age`, None as well when the
+    /// file is missing, unreadable or from another version, the database answers then
+    pub fn read(&self, now: DateTime<Utc>, max_age: Duration) -> Option<Status> {
+        let status = self.entries().remove(&self.key)?;
+        // one from the future is from a clock that was wrong, it is as unknown as an old one
+        (status.updated_at <= now && now - status.updated_at <= max_age).then_some(status)
+    }
+
+    /// whether anything was ever cached for the database, only then is it kept up to date
+    pub fn is_used(&self) -> bool {
+        self.entries().contains_key(&self.key)
+    }
+
+    /// replace the status of the database, a broken file is started anew
+    pub fn write(&self, status: Status) -> anyhow::Result<()> {
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        // two processes updating different databases would otherwise each write back the
+        // entries they read before the other one's, the lock is released when it is dropped
+        let lock = std::fs::File::create(self.path.with_extension("lock"))?;
+        lock.lock()?;
+        let mut entries = self.entries();
+        entries.insert(self.key.clone(), status);
+        // a prompt reading at the same time sees the old file or the new one, never half of it
+        let temporary = self.path.with_extension(format!("{}.tmp", std::process::id()));
+        std::fs::write(&temporary, serde_json::to_string(&entries)?)?;
+        std::fs::rename(&temporary, &self.path).inspect_err(|_| {
+            let _ = std::fs::remove_file(&temporary);
+        })?;
+        Ok(())
+    }
+
+    fn entries(&self) -> BTreeMap<String, Status> {
+        std::fs::read_to_string(&self.path)
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+}
+
+/*-----------------------------------*/
+/*               tests               */
+/*-----------------------------------*/
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(text: &str) -> DateTime<Utc> {
+        text.parse().unwrap()
+    }
+
+    fn status(pending: i64, updated_at: &str) -> Status {
+        Status {
+            pending,
+            top: Some(String::from("send invoice")),
+            updated_at: at(updated_at),
+        }
+    }
+
+    #[test]
+    fn test_staleness() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = StatusCache {
+            path: dir.path().join("db_test").join("status.json"),
+            key: String::from("1a2b3c4d:todos"),
+        };
+        let now = at("2024-05-04T12:00:00Z");
+        let minute = Duration::seconds(60);
+        // nothing cached yet
+        assert_eq!(cache.read(now, minute), None);
+        assert!(!cache.is_used());
+
+        cache.write(status(7, "2024-05-04T11:59:30Z")).unwrap();
+        assert!(cache.is_used());
+        assert_eq!(cache.read(now, minute), Some(status(7, "2024-05-04T11:59:30Z")));
+        assert_eq!(cache.read(now, Duration::seconds(10)), None);
+        assert_eq!(cache.read(at("2024-05-04T12:00:31Z"), minute), None);
+        assert_eq!(cache.read(at("2024-05-04T11:00:00Z"), minute), None);
+        // no temporary file is left behind, only the lock
+        assert_eq!(std::fs::read_dir(cache.path.parent().unwrap()).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn test_other_database() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("status.json");
+        let home = StatusCache {
+            path: path.clone(),
+            key: String::from("1a2b3c4d:todos"),
+        };
+        let work = StatusCache {
+            path,
+            key: String::from("9f8e7d6c:todos"),
+        };
+        let now = at("2024-05-04T12:00:00Z");
+        home.write(status(7, "2024-05-04T12:00:00Z")).unwrap();
+        assert_eq!(work.read(now, Duration::seconds(60)), None);
+        assert!(!work.is_used());
+
+        // both are kept
+        work.write(status(2, "2024-05-04T12:00:00Z")).unwrap();
+        assert_eq!(home.read(now, Duration::seconds(60)).unwrap().pending, 7);
+        assert_eq!(work.read(now, Duration::seconds(60)).unwrap().pending, 2);
+    }
+
+    #[test]
+    fn test_concurrent_writes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("status.json");
+        let threads: Vec<_> = (0..8)
+            .map(|index| {
+                let cache = StatusCache {
+                    path: path.clone(),
+                    key: format!("{index}:todos"),
+                };
+                std::thread::spawn(move || {
+                    for pending in 0..20 {
+                        cache.write(status(pending, "2024-05-04T12:00:00Z")).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        // every database keeps its entry
+        let cache = StatusCache {
+            path,
+            key: String::new(),
+        };
+        assert_eq!(cache.entries().len(), 8);
+    }
+
+    #[test]
+    fn test_corrupt_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = StatusCache {
+            path: dir.path().join("status.json"),
+            key: String::from("1a2b3c4d:todos"),
+        };
+        let now = at("2024-05-04T12:00:00Z");
+        for broken in ["{\"1a2b3c4d:todos\": {\"pending\": 7", "[]", "{\"1a2b3c4d:todos\": {\"pending\": \"x\"}}", ""] {
+            std::fs::write(&cache.path, broken).unwrap();
+            assert_eq!(cache.read(now, Duration::seconds(60)), None, "{broken}");
+            // written anew instead of failing
+            cache.write(status(3, "2024-05-04T12:00:00Z")).unwrap();
+            assert_eq!(cache.read(now, Duration::seconds(60)).unwrap().pending, 3);
+        }
+    }
+}
@@ -0,0 +1,130 @@
+use argon2::Argon2;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use chacha20poly1305::aead::{rand_core::RngCore, Aead, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+
+const NONCE_LEN: usize = 12;
+const TAG_LEN: usize = 16;
+pub const SALT_LEN: usize = 16;
+
+/// symmetric cipher for values stored in the database
+/// stored values are base64 of the random nonce followed by the ciphertext
+pub struct Cipher {
+    cipher: ChaCha20Poly1305,
+}
+
+impl Cipher {
+    /// derive the key from the passphrase with argon2
+    pub fn from_passphrase(passphrase: &str, salt: &[u8]) -> anyhow::Result<Self> {
+        let mut key = Key::default();
+        Argon2::default()
+            .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+            .map_err(|e| anyhow::anyhow!("Key derivation failed: {e}"))?;
+
+        Ok(Self {
+            cipher: ChaCha20Poly1305::new(&key),
+        })
+    }
+
+    pub fn encrypt(&self, plaintext: &str) -> anyhow::Result<String> {
+        let mut nonce = [0u8; NONCE_LEN];
+        OsRng.fill_bytes(&mut nonce);
+
+        let ciphertext = self
+            .cipher
+            .encrypt(Nonce::from_slice(&nonce), plaintext.as_bytes())
+            .map_err(|_| anyhow::anyhow!("Encryption failed"))?;
+
+        let mut stored = nonce.to_vec();
+        stored.extend(ciphertext);
+        Ok(BASE64.encode(stored))
+    }
+
+    /// fails both for a wrong key and for a value that was tampered with,
+    /// the AEAD tag can't tell these apart
+    pub fn decrypt(&self, stored: &str) -> anyhow::Result<String> {
+        let cannot_decrypt = || anyhow::anyhow!("cannot decrypt — wrong passphrase?");
+
+        let stored = BASE64.decode(stored).map_err(|_| cannot_decrypt())?;
+        if stored.len() < NONCE_LEN {
+            return Err(cannot_decrypt());
+        }
+        let (nonce, ciphertext) = stored.split_at(NONCE_LEN);
+
+        let plaintext = self
+            .cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| cannot_decrypt())?;
+        String::from_utf8(plaintext).map_err(|_| cannot_decrypt())
+    }
+}
+
+/// whether the stored value can be one that encrypt made, plain text mostly isn't even base64
+pub fn is_ciphertext(stored: &str) -> bool {
+    BASE64.decode(stored).is_ok_and(|stored| stored.len() >= NONCE_LEN + TAG_LEN)
+}
+
+/// random salt for the key derivation, stored in the database next to the data
+pub fn generate_salt() -> String {
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    BASE64.encode(salt)
+}
+
+pub fn decode_salt(salt: &str) -> anyhow::Result<Vec<u8>> {
+    Ok(BASE64.decode(salt)?)
+}
+
+/*-----------------------------------*/
+/*               tests               */
+/*-----------------------------------*/
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SALT: &[u8] = b"0123456789abcdef";
+
+    #[test]
+    fn test_round_trip() {
+        let cipher = Cipher::from_passphrase("secret", SALT).unwrap();
+        let stored = cipher.encrypt("buy milk ✓").unwrap();
+
+        assert!(!stored.contains("milk"));
+        assert_eq!(cipher.decrypt(&stored).unwrap(), "buy milk ✓");
+        // random nonces, so the same text never encrypts the same way twice
+        assert_ne!(cipher.encrypt("buy milk ✓").unwrap(), stored);
+    }
+
+    #[test]
+    fn test_wrong_passphrase() {
+        let stored = Cipher::from_passphrase("secret", SALT)
+            .unwrap()
+            .encrypt("buy milk")
+            .unwrap();
+        let wrong = Cipher::from_passphrase("guess", SALT).unwrap();
+
+        let err = wrong.decrypt(&stored).unwrap_err();
+        assert_eq!(err.to_string(), "cannot decrypt — wrong passphrase?");
+    }
+
+    #[test]
+    fn test_tampered_value() {
+        let cipher = Cipher::from_passphrase("secret", SALT).unwrap();
+        let mut stored = BASE64.decode(cipher.encrypt("buy milk").unwrap()).unwrap();
+        let last = stored.len() - 1;
+        stored[last] ^= 1;
+
+        assert!(cipher.decrypt(&BASE64.encode(stored)).is_err());
+        assert!(cipher.decrypt("not base64!").is_err());
+        assert!(cipher.decrypt("").is_err());
+    }
+
+    #[test]
+    fn test_is_ciphertext() {
+        let cipher = Cipher::from_passphrase("secret", SALT).unwrap();
+        assert!(is_ciphertext(&cipher.encrypt("").unwrap()));
+        assert!(!is_ciphertext("buy milk"));
+        // base64, but too short to hold the nonce and the tag
+        assert!(!is_ciphertext("bWlsaw=="));
+    }
+}
@@ -0,0 +1,120 @@
+use chrono::{DateTime, Datelike, Duration, FixedOffset, Local, NaiveDate, Utc};
+use serde::Serialize;
+
+/// `local`, `UTC` or an offset like `+02:00`, the local one is the offset it has at `now`
+pub fn parse_offset(text: &str, now: DateTime<Utc>) -> anyhow::Result<FixedOffset> {
+    match text.trim() {
+        "local" => return Ok(*now.with_timezone(&Local).offset()),
+        "utc" | "UTC" | "Z" => return Ok(FixedOffset::east_opt(0).expect("zero is a valid offset")),
+        _ => {}
+    }
+    text.trim()
+        .parse::<FixedOffset>()
+        .map_err(|_| anyhow::anyhow!("Cannot parse time zone {text}, expected local, UTC or e.g. +02:00"))
+}
+
+// how regularly todos get completed
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize)]
+pub struct Streaks {
+    /// days in a row up to today, or up to yesterday while nothing was completed today yet
+    pub current: u32,
+    pub longest: u32,
+    /// completions since Monday
+    pub this_week: i64,
+    pub last_week: i64,
+}
+
+/// streaks over completions per day, `days` sorted by day as the database returns them
+pub fn streaks(days: &[(NaiveDate, i64)], today: NaiveDate) -> Streaks {
+    let mut result = Streaks::default();
+    let mut run = 0;
+    let mut previous: Option<NaiveDate> = None;
+    for (day, _) in days.iter().filter(|(day, count)| *count > 0 && *day <= today) {
+        run = match previous {
+            Some(previous) if previous.succ_opt() == Some(*day) => run + 1,
+            Some(previous) if previous == *day => run,
+            _ => 1,
+        };
+        result.longest = result.longest.max(run);
+        previous = Some(*day);
+    }
+    // the streak only breaks once a whole day went by without a completion
+    let yesterday = today.pred_opt();
+    if previous == Some(today) || (previous.is_some() && previous == yesterday) {
+        result.current = run;
+    }
+
+    let monday = today - Duration::days(i64::from(today.weekday().num_days_from_monday()));
+    let last_monday = monday - Duration::days(7);
+    for (day, count) in days {
+        if (monday..=today).contains(day) {
+            result.this_week += count;
+        } else if (last_monday..monday).contains(day) {
+            result.last_week += count;
+        }
+    }
+    result
+}
+
+/*-----------------------------------*/
+/*               tests               */
+/*-----------------------------------*/
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(text: &str) -> DateTime<Utc> {
+        text.parse().unwrap()
+    }
+
+    #[test]
+    fn test_parse_offset() {
+        let now = at("2024-05-04T12:30:00Z");
+        assert_eq!(parse_offset("UTC", now).unwrap().local_minus_utc(), 0);
+        assert_eq!(parse_offset("+02:00", now).unwrap().local_minus_utc(), 7200);
+        assert_eq!(parse_offset("-05:30", now).unwrap().local_minus_utc(), -19800);
+        assert!(parse_offset("local", now).is_ok());
+        assert!(parse_offset("Europe/Prague", now).is_err());
+    }
+
+    #[test]
+    fn test_streaks() {
+        let day = |text: &str| text.parse::<NaiveDate>().unwrap();
+        // 2024-05-08 is a Wednesday
+        let today = day("2024-05-08");
+        let days = vec![
+            (day("2024-04-20"), 1),
+            (day("2024-04-21"), 1),
+            (day("2024-04-22"), 1),
+            (day("2024-04-23"), 4),
+            (day("2024-05-02"), 2),
+            (day("2024-05-06"), 1),
+            (day("2024-05-07"), 3),
+            (day("2024-05-08"), 1),
+        ];
+        assert_eq!(
+            streaks(&days, today),
+            Streaks {
+                current: 3,
+                longest: 4,
+                this_week: 5,
+                last_week: 2,
+            }
+        );
+        // nothing yet today, yesterday's streak is still alive
+        assert_eq!(streaks(&days[..7], today).current, 2);
+        // a whole day went by
+        assert_eq!(streaks(&days[..7], day("2024-05-09")).current, 0);
+        assert_eq!(streaks(&[], today), Streaks::default());
+        // a single day, and days in the future of a clock that is off
+        assert_eq!(streaks(&[(today, 1)], today).current, 1);
+        assert_eq!(streaks(&[(day("2024-05-09"), 1)], today), Streaks::default());
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn test_parse_offset_any_text(text in "\\PC*") {
+            let _ = parse_offset(&text, at("2024-05-04T12:30:00Z"));
+        }
+    }
+}
@@ -0,0 +1,124 @@
+use sqlx::sqlite::SqlitePoolOptions;
+use std::io::Write;
+use structopt::StructOpt;
+
+use crate::messages::Messages;
+use crate::render::{self, Charset, Style};
+use crate::{handle_command, Args, DBTrait, SqliteDBStruct, TableName};
+
+/// todos the demo database starts with, description and whether it is done
+const FIXTURE: &[(&str, bool)] = &[
+    ("Buy milk", true),
+    ("Send the Q3 invoice to ACME", false),
+    ("Read https://sqlite.org/lang_upsert.html", false),
+    ("Renew passport before the summer trip", true),
+    ("Fix the flaky CI job", false),
+];
+
+/// commands the demo runs, as they would be typed after `todo`
+const SCRIPT: &[&[&str]] = &[
+    &["add", "Water the plants"],
+    &["list"],
+    &["done", "3"],
+    &["list"],
+];
+
+/// run the script on a throwaway in-memory database, printing every command and its output
+pub async fn run(charset: Charset, messages: Messages, writer: &mut impl Write) -> anyhow::Result<()> {
+    // every connection to :memory: is a separate database, so keep just one
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await?;
+    let database = SqliteDBStruct::new(pool, TableName::default());
+    database.create_table().await?;
+    for (description, done) in FIXTURE {
+        let id = database.add_todo(description.to_string()).await?.result;
+        if *done {
+            database.complete_todo(id).await?;
+        }
+    }
+
+    for (i, command) in SCRIPT.iter().enumerate() {
+        if i > 0 {
+            writeln!(writer)?;
+        }
+        let typed: Vec<String> = command.iter().map(|arg| shell_quote(arg)).collect();
+        writeln!(writer, "$ todo {}", typed.join(" "))?;
+
+        let args = Args::from_iter_safe(std::iter::once("todo").chain(command.iter().copied()))?;
+        let outcome = handle_command(&args, &database).await?;
+        let style = Style {
+            charset,
+            messages,
+            ..Default::default()
+        };
+        render::render(&outcome, args.format, style, writer)?;
+    }
+    Ok(())
+}
+
+/// quote the argument the way it would have to be typed into a shell
+fn shell_quote(arg: &str) -> String {
+    if arg.chars().all(|c| c.is_ascii_alphanumeric() || "-_./:".contains(c)) {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', r"'\''"))
+    }
+}
+
+/*-----------------------------------*/
+/*               tests               */
+/*-----------------------------------*/
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the demo output ends up in the docs, so any change to it has to be deliberate
+    #[tokio::test]
+    async fn test_demo_output() {
+        let mut out = Vec::new();
+        run(Charset::Unicode, Messages::default(), &mut out).await.unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "\
+$ todo add 'Water the plants'
+Adding new todo with description 'Water the plants'
+Added new todo with id 6
+
+$ todo list
+Printing list of all todos
+- [x] 1: Buy milk
+- [ ] 2: Send the Q3 invoice to ACME
+- [ ] 3: Read https://sqlite.org/lang_upsert.html
+- [x] 4: Renew passport before the summer trip
+- [ ] 5: Fix the flaky CI job
+- [ ] 6: Water the plants
+2/6 done 33%
+
+$ todo done 3
+Marking todo 3 as done
+Todo 3 is marked as done
+
+$ todo list
+Printing list of all todos
+- [x] 1: Buy milk
+- [ ] 2: Send the Q3 invoice to ACME
+- [x] 3: Read https://sqlite.org/lang_upsert.html
+- [x] 4: Renew passport before the summer trip
+- [ ] 5: Fix the flaky CI job
+- [ ] 6: Water the plants
+3/6 done 50%
+"
+        );
+    }
+
+    #[test]
+    fn test_shell_quote() {
+        assert_eq!(shell_quote("list"), "list");
+        assert_eq!(shell_quote("--with-time"), "--with-time");
+        assert_eq!(shell_quote("buy milk"), "'buy milk'");
+        assert_eq!(shell_quote("Bob's task"), r"'Bob'\''s task'");
+    }
+}
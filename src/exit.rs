@@ -0,0 +1,171 @@
+// the exit codes scripts can branch on, every error is one of these failures
+
+use std::fmt::Display;
+
+/// the end of --help, keep it in line with Failure::code
+pub const HELP: &str = "EXIT CODES:
+    0    success
+    2    usage, the arguments, the config or an id don't make sense
+    3    not found, no todo has the id, or nothing to complete for done --strict
+    4    unavailable, the database can't be reached, is busy or can't be used as it is
+    5    conflict, a blocked todo, a todo done already for done --strict, or todos changed meanwhile
+    6    cancelled, by the answer to a question, for want of one, or by --timeout-secs";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Failure {
+    Usage,
+    NotFound,
+    Unavailable,
+    Conflict,
+    Cancelled,
+}
+
+impl Failure {
+    pub fn code(self) -> i32 {
+        match self {
+            Failure::Usage => 2,
+            Failure::NotFound => 3,
+            Failure::Unavailable => 4,
+            Failure::Conflict => 5,
+            Failure::Cancelled => 6,
+        }
+    }
+
+    /// an error ending the program with the code of this failure
+    pub fn error(self, message: impl Display) -> anyhow::Error {
+        anyhow::Error::new(Failed {
+            failure: self,
+            message: message.to_string(),
+        })
+    }
+}
+
+#[derive(Debug)]
+struct Failed {
+    failure: Failure,
+    message: String,
+}
+
+impl Display for Failed {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for Failed {}
+
+/// what the error stands for, the messages without a failure of their own are about
+/// arguments, config or todos that don't fit together
+pub fn failure(err: &anyhow::Error) -> Failure {
+    err.chain()
+        .find_map(|cause| {
+            if let Some(failed) = cause.downcast_ref::<Failed>() {
+                Some(failed.failure)
+            } else if let Some(refusal) = cause.downcast_ref::<crate::messages::Refusal>() {
+                Some(refusal.failure())
+            } else if cause.is::<structopt::clap::Error>() {
+                Some(Failure::Usage)
+            } else if let Some(err) = cause.downcast_ref::<sqlx::Error>() {
+                Some(database(err))
+            } else if cause.is::<std::io::Error>() {
+                Some(Failure::Unavailable)
+            } else {
+                None
+            }
+        })
+        .unwrap_or(Failure::Usage)
+}
+
+/// a bad URL, a constraint the change broke, or a database that can't be worked with
+fn database(err: &sqlx::Error) -> Failure {
+    match err {
+        sqlx::Error::Configuration(_) => Failure::Usage,
+        sqlx::Error::Database(err) if err.kind() != sqlx::error::ErrorKind::Other => Failure::Conflict,
+        // unreachable, busy, or it answered but not the way this program expects
+        _ => Failure::Unavailable,
+    }
+}
+
+/// whether the database couldn't be reached or used at all, rather than turning down what was asked of it,
+/// a wrong password or table name would be no different on a fallback
+pub fn unreachable(err: &anyhow::Error) -> bool {
+    err.chain().any(|cause| {
+        if let Some(failed) = cause.downcast_ref::<Failed>() {
+            failed.failure == Failure::Unavailable
+        } else if let Some(err) = cause.downcast_ref::<sqlx::Error>() {
+            unavailable(err)
+        } else {
+            cause.is::<std::io::Error>()
+        }
+    })
+}
+
+/// the server or file isn't there, refuses to talk, or is too busy to
+fn unavailable(err: &sqlx::Error) -> bool {
+    match err {
+        sqlx::Error::Io(_)
+        | sqlx::Error::Tls(_)
+        | sqlx::Error::PoolTimedOut
+        | sqlx::Error::PoolClosed
+        | sqlx::Error::WorkerCrashed => true,
+        sqlx::Error::Database(err) => match err.try_downcast_ref::<sqlx::sqlite::SqliteError>() {
+            // SQLITE_BUSY and SQLITE_CANTOPEN, the extended codes keep them in the low byte
+            Some(_) => err
+                .code()
+                .and_then(|code| code.parse::<i32>().ok())
+                .is_some_and(|code| matches!(code & 0xff, 5 | 14)),
+            // connection exceptions, too many connections and a server starting or stopping
+            None => err
+                .code()
+                .is_some_and(|code| code.starts_with("08") || matches!(&*code, "53300" | "57P01" | "57P03")),
+        },
+        _ => false,
+    }
+}
+
+/*-----------------------------------*/
+/*               tests               */
+/*-----------------------------------*/
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_failure() {
+        for failure in [
+            Failure::Usage,
+            Failure::NotFound,
+            Failure::Unavailable,
+            Failure::Conflict,
+            Failure::Cancelled,
+        ] {
+            let err = failure.error("Invalid id 9");
+            assert_eq!(super::failure(&err), failure);
+            assert_eq!(err.to_string(), "Invalid id 9");
+            // context on the way up keeps it
+            assert_eq!(super::failure(&err.context("while completing")), failure);
+            assert!(HELP.contains(&format!("\n    {}    ", failure.code())), "{failure:?}");
+        }
+        assert!(!HELP.contains("\n    1    "));
+        assert_eq!(super::failure(&anyhow::anyhow!("--interval-secs has to be at least 1")), Failure::Usage);
+        let refusal = crate::messages::Refusal::AlreadyTracked(4).error();
+        assert_eq!(super::failure(&refusal), Failure::Conflict);
+
+        let io = std::io::Error::new(std::io::ErrorKind::ConnectionRefused, "refused");
+        assert_eq!(super::failure(&sqlx::Error::Io(io).into()), Failure::Unavailable);
+        assert_eq!(super::failure(&sqlx::Error::PoolTimedOut.into()), Failure::Unavailable);
+        assert_eq!(super::failure(&sqlx::Error::RowNotFound.into()), Failure::Unavailable);
+        assert_eq!(super::failure(&sqlx::Error::Configuration("bad URL".into()).into()), Failure::Usage);
+    }
+
+    #[test]
+    fn test_unreachable() {
+        let io = std::io::Error::new(std::io::ErrorKind::ConnectionRefused, "refused");
+        assert!(unreachable(&sqlx::Error::Io(io).into()));
+        assert!(unreachable(&sqlx::Error::PoolTimedOut.into()));
+        assert!(unreachable(&Failure::Unavailable.error("The sqlite database todos.db is locked")));
+        assert!(!unreachable(&Failure::Usage.error("Invalid name \"team-a\"")));
+        assert!(!unreachable(&sqlx::Error::RowNotFound.into()));
+        assert!(!unreachable(&anyhow::anyhow!("Missing database name")));
+    }
+}
@@ -0,0 +1,143 @@
+use serde::Serialize;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+use crate::Todo;
+
+// how long to wait for the hook unless the config says otherwise
+pub const DEFAULT_TIMEOUT_SECS: u64 = 5;
+
+// command the config wants to know about every change
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hook {
+    pub command: String,
+    pub timeout: Duration,
+}
+
+// what the hook gets on stdin
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Event {
+    /// added, completed, reopened, edited, cleared, merged or purged
+    pub event: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub todo: Option<Todo>,
+    /// id of the todo merged away
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub removed: Option<i64>,
+    /// number of removed todos
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub count: Option<u64>,
+}
+
+impl Hook {
+    /// run the command through the shell with the event on stdin, $TODO_EVENT and $TODO_ID,
+    /// a hook still running after the timeout is left alone
+    pub fn run(&self, event: &Event) -> anyhow::Result<()> {
+        let mut command = if cfg!(target_os = "windows") {
+            let mut command = Command::new("cmd");
+            command.arg("/C");
+            command
+        } else {
+            let mut command = Command::new("sh");
+            command.arg("-c");
+            command
+        };
+        command
+            .arg(&self.command)
+            .env("TODO_EVENT", event.event)
+            .stdin(Stdio::piped())
+            // stdout belongs to the todos, --format json has to stay parsable
+            .stdout(Stdio::null());
+        match &event.todo {
+            Some(todo) => command.env("TODO_ID", todo.id.to_string()),
+            None => command.env_remove("TODO_ID"),
+        };
+
+        let mut child = command.spawn()?;
+        let payload = serde_json::to_string(event)?;
+        if let Some(mut stdin) = child.stdin.take() {
+            // a hook not interested in the payload may close stdin early
+            match writeln!(stdin, "{payload}") {
+                Err(err) if err.kind() != std::io::ErrorKind::BrokenPipe => return Err(err.into()),
+                _ => {}
+            }
+        }
+
+        let deadline = Instant::now() + self.timeout;
+        loop {
+            if let Some(status) = child.try_wait()? {
+                if !status.success() {
+                    return Err(anyhow::anyhow!("it exited with {status}"));
+                }
+                return Ok(());
+            }
+            if Instant::now() >= deadline {
+                return Err(anyhow::anyhow!(
+                    "still running after {}s, not waiting for it any longer",
+                    self.timeout.as_secs()
+                ));
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    /// run the hook, a failing hook never fails the command that triggered it
+    pub fn notify(&self, event: &Event) {
+        if let Err(err) = self.run(event) {
+            eprintln!("Hook {} failed: {err}", self.command);
+        }
+    }
+}
+
+/*-----------------------------------*/
+/*               tests               */
+/*-----------------------------------*/
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    fn hook(command: String, timeout: Duration) -> Hook {
+        Hook { command, timeout }
+    }
+
+    fn event() -> Event {
+        Event {
+            event: "added",
+            todo: Some(Todo {
+                id: 7,
+                description: String::from("buy milk"),
+                ..Default::default()
+            }),
+            removed: None,
+            count: None,
+        }
+    }
+
+    #[test]
+    fn test_payload_and_env() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("hook");
+        let command = format!("{{ cat; echo \"$TODO_EVENT $TODO_ID\"; }} > '{}'", path.display());
+        hook(command, Duration::from_secs(5)).run(&event()).unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "{\"event\":\"added\",\"todo\":{\"id\":7,\"description\":\"buy milk\",\"done\":false}}\nadded 7\n"
+        );
+    }
+
+    #[test]
+    fn test_failures() {
+        let err = hook(String::from("exit 3"), Duration::from_secs(5)).run(&event()).unwrap_err();
+        assert!(err.to_string().starts_with("it exited with"), "{err}");
+
+        // ignoring stdin is fine
+        hook(String::from("true"), Duration::from_secs(5)).run(&event()).unwrap();
+
+        let started = Instant::now();
+        let err = hook(String::from("sleep 5"), Duration::from_millis(100)).run(&event()).unwrap_err();
+        assert!(err.to_string().starts_with("still running"), "{err}");
+        assert!(started.elapsed() < Duration::from_secs(2));
+    }
+}
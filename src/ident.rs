@@ -0,0 +1,103 @@
+use regex::Regex;
+use std::sync::OnceLock;
+
+// names that become part of the SQL text instead of a bound parameter: the table
+// and schema names and the keys of custom fields, which sqlite puts into a JSON path
+
+// longest name shown in an error, a pasted novel shouldn't fill the terminal
+const SHOWN_CHARS: usize = 40;
+
+/// the name for an error message, cut short and with odd characters escaped
+fn shown(name: &str) -> String {
+    if name.chars().count() <= SHOWN_CHARS {
+        return format!("{name:?}");
+    }
+    let start: String = name.chars().take(SHOWN_CHARS).collect();
+    format!("{start:?}…")
+}
+
+/// plain identifiers only, so that nothing needs escaping,
+/// ASCII only so that a homoglyph can't pass for another table
+pub fn validate_identifier(name: &str) -> anyhow::Result<()> {
+    static IDENT_RE: OnceLock<Regex> = OnceLock::new();
+    // 63 is the postgres identifier limit, suffixes like _sessions have to fit in as well
+    let re = IDENT_RE.get_or_init(|| Regex::new(r"^[A-Za-z_][A-Za-z0-9_]{0,49}$").unwrap());
+
+    if !re.is_match(name) {
+        return Err(anyhow::anyhow!(
+            "Invalid name {}, use up to 50 letters, digits or underscores not starting with a digit",
+            shown(name)
+        ));
+    }
+    Ok(())
+}
+
+/// key of a custom field, `.` or `[` would reach into other parts of the JSON
+pub fn validate_field_name(key: &str) -> anyhow::Result<()> {
+    static KEY_RE: OnceLock<Regex> = OnceLock::new();
+    let re = KEY_RE.get_or_init(|| Regex::new(r"^[A-Za-z_][A-Za-z0-9_]{0,63}$").unwrap());
+
+    if !re.is_match(key) {
+        return Err(anyhow::anyhow!(
+            "Invalid field name {}, use up to 64 letters, digits or underscores not starting with a digit",
+            shown(key)
+        ));
+    }
+    Ok(())
+}
+
+/// the name in double quotes, which postgres and sqlite both read alike,
+/// embedded quotes doubled in case a name ever skips validate_identifier
+pub fn quote_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/*-----------------------------------*/
+/*               tests               */
+/*-----------------------------------*/
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // names somebody will try sooner or later
+    const ADVERSARIAL: [&str; 9] = [
+        "\"; DROP TABLE todos; --",
+        "todos; DROP TABLE todos",
+        "todos\"",
+        "todos'",
+        "todos--",
+        "to dos",
+        // cyrillic а in place of the latin one
+        "tаsks",
+        "todos\0",
+        "",
+    ];
+
+    #[test]
+    fn test_validate() {
+        for name in ADVERSARIAL {
+            assert!(validate_identifier(name).is_err(), "{name:?}");
+            assert!(validate_field_name(name).is_err(), "{name:?}");
+        }
+        assert!(validate_identifier("todos_2024").is_ok());
+        assert!(validate_identifier(&"t".repeat(51)).is_err());
+        assert!(validate_field_name("sprint").is_ok());
+        assert!(validate_field_name("a.b").is_err());
+        assert!(validate_field_name("a[0]").is_err());
+        assert!(validate_field_name(&"k".repeat(65)).is_err());
+
+        // long names are cut in the message
+        let error = validate_identifier(&"x".repeat(10_000)).unwrap_err().to_string();
+        assert!(error.starts_with(&format!("Invalid name \"{}\"…, use", "x".repeat(40))), "{error}");
+        assert_eq!(
+            validate_identifier("a\"b").unwrap_err().to_string(),
+            "Invalid name \"a\\\"b\", use up to 50 letters, digits or underscores not starting with a digit"
+        );
+    }
+
+    #[test]
+    fn test_quote_identifier() {
+        assert_eq!(quote_identifier("todos"), "\"todos\"");
+        assert_eq!(quote_identifier("\"; DROP TABLE todos; --"), "\"\"\"; DROP TABLE todos; --\"");
+    }
+}
@@ -0,0 +1,242 @@
+use chacha20poly1305::aead::{rand_core::RngCore, OsRng};
+use std::str::FromStr;
+
+// crockford's base32 alphabet, lowercase and without the easily confused i, l, o, u
+const ALPHABET: &[u8; 32] = b"0123456789abcdefghjkmnpqrstvwxyz";
+const HASH_LEN: usize = 6;
+// six base32 characters hold 30 bits, split into two halves for the feistel network
+const HALF_BITS: u32 = 15;
+const HALF_MASK: u64 = (1 << HALF_BITS) - 1;
+const ROUNDS: u64 = 4;
+
+// how ids are shown, chosen on the command line
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum IdStyle {
+    #[default]
+    Integer,
+    Hash,
+}
+
+impl FromStr for IdStyle {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "integer" => Ok(Self::Integer),
+            "hash" => Ok(Self::Hash),
+            _ => Err(anyhow::anyhow!("Unknown id style {s}, expected integer or hash")),
+        }
+    }
+}
+
+// id style together with the salt of the database for hash ids
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum IdFormat {
+    #[default]
+    Integer,
+    Hash(u64),
+}
+
+impl IdFormat {
+    /// the id as the user sees it, ids too large for a hash stay integers and so do those
+    /// whose hash is only digits, typed digits are always taken for an integer id
+    pub fn show(self, id: i64) -> String {
+        match self {
+            Self::Hash(salt) => encode(id, salt)
+                .filter(|hash| !hash.bytes().all(|c| c.is_ascii_digit()))
+                .unwrap_or_else(|| id.to_string()),
+            Self::Integer => id.to_string(),
+        }
+    }
+
+    /// the integer id for a typed hash, None when the text isn't one
+    pub fn decode(self, text: &str) -> Option<i64> {
+        match self {
+            Self::Hash(salt) => decode(text, salt),
+            Self::Integer => None,
+        }
+    }
+}
+
+/// the error for digits too many for any id
+pub fn out_of_range(text: &str) -> String {
+    format!("Id {text} is out of range, the largest is {}", i64::MAX)
+}
+
+/// an integer id as typed, without the prefix matching the commands do
+pub fn parse_integer(text: &str) -> anyhow::Result<i64> {
+    if text.is_empty() || !text.bytes().all(|c| c.is_ascii_digit()) {
+        return Err(anyhow::anyhow!("Invalid id {text}, expected a number"));
+    }
+    text.parse().map_err(|_| anyhow::anyhow!(out_of_range(text)))
+}
+
+/// random salt for a database starting to use hash ids
+pub fn generate_salt() -> u64 {
+    OsRng.next_u64()
+}
+
+/// six character hash of the id, a permutation of all ids below 2^30 so that it can be reversed
+pub fn encode(id: i64, salt: u64) -> Option<String> {
+    let id = u64::try_from(id).ok().filter(|id| *id <= HALF_MASK << HALF_BITS | HALF_MASK)?;
+
+    let (mut left, mut right) = (id >> HALF_BITS, id & HALF_MASK);
+    for round in 0..ROUNDS {
+        (left, right) = (right, left ^ scramble(right, salt, round));
+    }
+    let mut value = left << HALF_BITS | right;
+
+    let mut hash = [0u8; HASH_LEN];
+    for c in hash.iter_mut().rev() {
+        *c = ALPHABET[(value & 31) as usize];
+        value >>= 5;
+    }
+    Some(String::from_utf8(hash.to_vec()).unwrap())
+}
+
+pub fn decode(hash: &str, salt: u64) -> Option<i64> {
+    if hash.len() != HASH_LEN {
+        return None;
+    }
+    let mut value = 0u64;
+    for c in hash.bytes() {
+        let digit = ALPHABET.iter().position(|a| *a == c.to_ascii_lowercase())?;
+        value = value << 5 | digit as u64;
+    }
+
+    let (mut left, mut right) = (value >> HALF_BITS, value & HALF_MASK);
+    for round in (0..ROUNDS).rev() {
+        (left, right) = (right ^ scramble(left, salt, round), left);
+    }
+    Some((left << HALF_BITS | right) as i64)
+}
+
+/// round function of the feistel network, splitmix64 of the half, the salt and the round
+fn scramble(half: u64, salt: u64, round: u64) -> u64 {
+    let mut z = half ^ salt ^ round.wrapping_mul(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    (z ^ (z >> 31)) & HALF_MASK
+}
+
+/*-----------------------------------*/
+/*               tests               */
+/*-----------------------------------*/
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAX_ID: i64 = (1 << 30) - 1;
+
+    #[test]
+    fn test_edges() {
+        for salt in [0, 1, 0xdead_beef, u64::MAX] {
+            for id in [0, 1, 2, 31, 32, 1 << 15, MAX_ID - 1, MAX_ID] {
+                assert_eq!(decode(&encode(id, salt).unwrap(), salt), Some(id), "id {id}, salt {salt}");
+            }
+        }
+        // the salt decides, so databases don't share hashes
+        assert_ne!(encode(1, 1), encode(1, 2));
+        assert_ne!(decode(&encode(7, 1).unwrap(), 2), Some(7));
+    }
+
+    #[test]
+    fn test_digit_hashes_stay_integers() {
+        let salt = 0xdead_beef;
+        let id = (0..).find(|id| encode(*id, salt).unwrap().bytes().all(|c| c.is_ascii_digit())).unwrap();
+        assert_eq!(IdFormat::Hash(salt).show(id), id.to_string());
+    }
+
+    #[test]
+    fn test_out_of_range() {
+        assert_eq!(encode(-1, 0), None);
+        assert_eq!(encode(MAX_ID + 1, 0), None);
+        assert_eq!(IdFormat::Hash(0).show(MAX_ID + 1), (MAX_ID + 1).to_string());
+        assert_eq!(IdFormat::Integer.show(12), "12");
+
+        assert_eq!(decode("abcde", 0), None);
+        assert_eq!(decode("abcdefg", 0), None);
+        // not in the alphabet
+        assert_eq!(decode("abcdeu", 0), None);
+        assert_eq!(decode("ábcde", 0), None);
+        assert_eq!(IdFormat::Integer.decode("abcdef"), None);
+    }
+
+    #[test]
+    fn test_parse_integer() {
+        assert_eq!(parse_integer("12").unwrap(), 12);
+        assert_eq!(parse_integer("9223372036854775807").unwrap(), i64::MAX);
+        assert_eq!(parse_integer("0009").unwrap(), 9);
+        for (text, expected) in [
+            ("9223372036854775808", "Id 9223372036854775808 is out of range, the largest is 9223372036854775807"),
+            ("99999999999999999999", "Id 99999999999999999999 is out of range"),
+            ("", "Invalid id , expected a number"),
+            ("-5", "Invalid id -5, expected a number"),
+            ("+5", "Invalid id +5, expected a number"),
+            ("1e3", "Invalid id 1e3, expected a number"),
+            ("١٢", "Invalid id ١٢, expected a number"),
+        ] {
+            let err = parse_integer(text).unwrap_err().to_string();
+            assert!(err.starts_with(expected), "{text}: {err}");
+        }
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn test_parse_integer_any_text(text in "\\PC*") {
+            let _ = parse_integer(&text);
+        }
+
+        // out of range exactly when the digits without leading zeros are more than i64::MAX
+        #[test]
+        fn test_parse_integer_digits(digits in "[0-9]{1,40}") {
+            let significant = digits.trim_start_matches('0');
+            let max = i64::MAX.to_string();
+            let fits = significant.len() < max.len() || significant.len() == max.len() && *significant <= *max;
+            match parse_integer(&digits) {
+                Ok(id) => {
+                    proptest::prop_assert!(fits);
+                    proptest::prop_assert_eq!(id.to_string(), if significant.is_empty() { "0" } else { significant });
+                }
+                Err(err) => {
+                    proptest::prop_assert!(!fits);
+                    proptest::prop_assert_eq!(err.to_string(), out_of_range(&digits));
+                }
+            }
+        }
+
+        #[test]
+        fn test_round_trip(id in 0..=MAX_ID, salt in proptest::num::u64::ANY) {
+            let hash = encode(id, salt).unwrap();
+            proptest::prop_assert_eq!(hash.len(), 6);
+            proptest::prop_assert_eq!(decode(&hash, salt), Some(id));
+            proptest::prop_assert_eq!(decode(&hash.to_uppercase(), salt), Some(id));
+        }
+
+        // a permutation, two ids never share a hash
+        #[test]
+        fn test_hashes_are_distinct(first in 0..=MAX_ID, second in 0..=MAX_ID, salt in proptest::num::u64::ANY) {
+            proptest::prop_assume!(first != second);
+            proptest::prop_assert_ne!(encode(first, salt), encode(second, salt));
+        }
+
+        // what is shown resolves back, a hash is never mistaken for digits
+        #[test]
+        fn test_show_is_unambiguous(id in 0..=MAX_ID, salt in proptest::num::u64::ANY) {
+            let shown = IdFormat::Hash(salt).show(id);
+            if shown.bytes().all(|c| c.is_ascii_digit()) {
+                proptest::prop_assert_eq!(shown, id.to_string());
+            } else {
+                proptest::prop_assert_eq!(decode(&shown, salt), Some(id));
+            }
+        }
+
+        // any text that decodes is the hash of what it decodes to
+        #[test]
+        fn test_decode_any_text(text in "\\PC{0,8}", salt in proptest::num::u64::ANY) {
+            if let Some(id) = decode(&text, salt) {
+                proptest::prop_assert_eq!(encode(id, salt), Some(text.to_ascii_lowercase()));
+            }
+        }
+    }
+}
@@ -0,0 +1,491 @@
+use std::fmt::Display;
+
+use crate::exit::Failure;
+
+// languages the plain output is available in
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Lang {
+    #[default]
+    English,
+    Czech,
+}
+
+impl Lang {
+    /// language of a locale like `cs_CZ.UTF-8` or a code like `cs`, English for anything unknown
+    pub fn from_locale(locale: &str) -> Self {
+        let code = locale.split(['_', '-', '.', '@']).next().unwrap_or_default();
+        match code.to_lowercase().as_str() {
+            "cs" | "cz" => Self::Czech,
+            _ => Self::English,
+        }
+    }
+
+    /// --lang if given, otherwise the first locale variable that is set, like for the C library
+    pub fn detect(lang: Option<&str>, variables: [Option<String>; 3]) -> Self {
+        match lang {
+            Some(lang) => Self::from_locale(lang),
+            None => variables
+                .into_iter()
+                .flatten()
+                .find(|value| !value.is_empty())
+                .map_or(Self::English, |locale| Self::from_locale(&locale)),
+        }
+    }
+}
+
+// every text of the plain output, `{}` are filled in order
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Message {
+    Adding,
+    Added,
+    Marking,
+    MarkedDone,
+    AlreadyDone,
+    UndoHint,
+    Reopened,
+    CompletedMatching,
+    NoneMatching,
+    InvalidId,
+    NotANumber,
+    MergeIntoItself,
+    AlreadyTracked,
+    AmbiguousId,
+    Pinned,
+    Unpinned,
+    Moved,
+    Estimated,
+    Edited,
+    Clearing,
+    Cleared,
+    Opening,
+    Merging,
+    Merged,
+    Summary,
+    Workload,
+    Tenant,
+    NoTenants,
+    PendingSummary,
+    WorkloadUnestimated,
+    CurrentStreak,
+    LongestStreak,
+    Weeks,
+    ShowTodo,
+    Status,
+    StatusDone,
+    StatusPending,
+    Tracked,
+    Estimate,
+    StartedTracking,
+    StoppedTracking,
+    NotTracking,
+    WouldPurge,
+    Purged,
+    MaintenanceDone,
+    SizeBefore,
+    SizeAfter,
+    Bytes,
+    UnknownSize,
+    Ping,
+    SchemaVersion,
+    NoMigrations,
+    Schema,
+    SchemaAdded,
+    SchemaAsExpected,
+    Rows,
+    Size,
+    Ssl,
+    Password,
+    Profile,
+    Url,
+    Intact,
+    IntegrityCheck,
+    Salvaged,
+    Backend,
+    Statement,
+    Plan,
+    ListHeader,
+    PendingListHeader,
+    Source,
+    GroupPending,
+    GroupDone,
+    BlockedBy,
+    Progress,
+    NextPage,
+    Skipped,
+}
+
+fn english(message: Message) -> &'static str {
+    match message {
+        Message::Adding => "Adding new todo with description '{}'",
+        Message::Added => "Added new todo with id {}",
+        Message::Marking => "Marking todo {} as done",
+        Message::MarkedDone => "Todo {} is marked as done",
+        Message::AlreadyDone => "Todo {} was already done",
+        Message::UndoHint => "Run `todo undo` within {}s to reopen todo {}",
+        Message::Reopened => "Todo {} is pending again",
+        Message::CompletedMatching => "Completed {} todos matching '{}'",
+        Message::NoneMatching => "No pending todos match '{}'",
+        Message::InvalidId => "Invalid id {}",
+        Message::NotANumber => "Invalid id {}, expected a number",
+        Message::MergeIntoItself => "Cannot merge todo {} into itself",
+        Message::AlreadyTracked => "Todo {} is already being tracked, stop it first",
+        Message::AmbiguousId => "Id prefix {} is ambiguous, candidates:\n{}",
+        Message::Pinned => "Todo {} is pinned",
+        Message::Unpinned => "Todo {} is no longer pinned",
+        Message::Moved => "Todo {} was moved",
+        Message::Estimated => "Todo {} is estimated at {}",
+        Message::Edited => "Todo {} is now '{}'",
+        Message::Clearing => "Clearing TODOs",
+        Message::Cleared => "TODOs were cleared",
+        Message::Opening => "Opening {}",
+        Message::Merging => "Merging todo {} into todo {}",
+        Message::Merged => "Todos were merged into todo {}",
+        Message::Summary => "{} todos, {} done, {} pending",
+        Message::Workload => "pending workload: {}",
+        Message::Tenant => "{}: {} todos",
+        Message::NoTenants => "No tenants",
+        Message::PendingSummary => "({} pending)",
+        Message::WorkloadUnestimated => "pending workload: {}, {} unestimated",
+        Message::CurrentStreak => "Current streak: {} days",
+        Message::LongestStreak => "Longest streak: {} days",
+        Message::Weeks => "This week: {} done, last week: {} done",
+        Message::ShowTodo => "Todo {}: {}",
+        Message::Status => "Status: {}",
+        Message::StatusDone => "done",
+        Message::StatusPending => "pending",
+        Message::Tracked => "Tracked: {}",
+        Message::Estimate => "Estimate: {}",
+        Message::StartedTracking => "Started tracking todo {}",
+        Message::StoppedTracking => "Stopped tracking todo {}",
+        Message::NotTracking => "No todo is being tracked",
+        Message::WouldPurge => "Would purge {} done todos",
+        Message::Purged => "Purged {} done todos",
+        Message::MaintenanceDone => "Maintenance done",
+        Message::SizeBefore => "size before: {}",
+        Message::SizeAfter => "size after: {}",
+        Message::Bytes => "{} bytes",
+        Message::UnknownSize => "unknown",
+        Message::Ping => "ping: {} ms",
+        Message::SchemaVersion => "schema version: {}",
+        Message::NoMigrations => "schema version: no migrations",
+        Message::Schema => "schema: {}",
+        Message::SchemaAdded => "schema: added {}",
+        Message::SchemaAsExpected => "schema: as expected",
+        Message::Rows => "rows: {}",
+        Message::Size => "size: {}",
+        Message::Password => "password: {}",
+        Message::Profile => "profile: {}",
+        Message::Url => "url: {}",
+        Message::Ssl => "ssl: {}",
+        Message::Intact => "The database is intact, there is nothing to recover",
+        Message::IntegrityCheck => "integrity check: {}",
+        Message::Salvaged => "Salvaged {} rows into {}, {} could not be read",
+        Message::Backend => "backend: {}",
+        Message::Statement => "statement: {}",
+        Message::Plan => "plan:",
+        Message::ListHeader => "Printing list of all todos",
+        Message::PendingListHeader => "Printing list of pending todos",
+        Message::Source => "Profile {}, database {}",
+        Message::GroupPending => "Pending ({})",
+        Message::GroupDone => "Done ({})",
+        Message::BlockedBy => "blocked by {}",
+        Message::Progress => "{}/{} done",
+        Message::NextPage => "Next page: --after-id {}",
+        Message::Skipped => "Skipped {}: {}",
+    }
+}
+
+// the doctor and explain output is for bug reports, it stays English
+fn czech(message: Message) -> Option<&'static str> {
+    Some(match message {
+        Message::Adding => "Přidávám nový úkol s popisem '{}'",
+        Message::Added => "Přidán nový úkol s id {}",
+        Message::Marking => "Označuji úkol {} jako hotový",
+        Message::MarkedDone => "Úkol {} je označen jako hotový",
+        Message::AlreadyDone => "Úkol {} už byl hotový",
+        Message::UndoHint => "Příkazem `todo undo` do {}s znovu otevřete úkol {}",
+        Message::Reopened => "Úkol {} je znovu nehotový",
+        Message::CompletedMatching => "Hotovo {} úkolů odpovídajících '{}'",
+        Message::NoneMatching => "Žádný čekající úkol neodpovídá '{}'",
+        Message::InvalidId => "Neplatné id {}",
+        Message::NotANumber => "Neplatné id {}, očekává se číslo",
+        Message::MergeIntoItself => "Úkol {} nelze sloučit sám se sebou",
+        Message::AlreadyTracked => "Úkol {} se už měří, nejdřív ho zastavte",
+        Message::AmbiguousId => "Předpona id {} není jednoznačná, možnosti:\n{}",
+        Message::Pinned => "Úkol {} je připnutý",
+        Message::Unpinned => "Úkol {} už není připnutý",
+        Message::Moved => "Úkol {} byl přesunut",
+        Message::Estimated => "Úkol {} má odhad {}",
+        Message::Edited => "Úkol {} teď zní '{}'",
+        Message::Clearing => "Mažu úkoly",
+        Message::Cleared => "Úkoly byly smazány",
+        Message::Opening => "Otevírám {}",
+        Message::Merging => "Slučuji úkol {} do úkolu {}",
+        Message::Merged => "Úkoly byly sloučeny do úkolu {}",
+        Message::Summary => "Úkolů: {}, hotovo: {}, zbývá: {}",
+        Message::Workload => "zbývající práce: {}",
+        Message::Tenant => "{}: {} úkolů",
+        Message::NoTenants => "Žádní nájemci",
+        Message::PendingSummary => "(čekajících: {})",
+        Message::WorkloadUnestimated => "zbývající práce: {}, bez odhadu: {}",
+        Message::CurrentStreak => "Aktuální série dní: {}",
+        Message::LongestStreak => "Nejdelší série dní: {}",
+        Message::Weeks => "Tento týden hotovo: {}, minulý týden: {}",
+        Message::ShowTodo => "Úkol {}: {}",
+        Message::Status => "Stav: {}",
+        Message::StatusDone => "hotovo",
+        Message::StatusPending => "čeká",
+        Message::Tracked => "Změřeno: {}",
+        Message::Estimate => "Odhad: {}",
+        Message::StartedTracking => "Měřím čas úkolu {}",
+        Message::StoppedTracking => "Měření úkolu {} zastaveno",
+        Message::NotTracking => "Žádný úkol se neměří",
+        Message::WouldPurge => "Smazalo by se hotových úkolů: {}",
+        Message::Purged => "Smazáno hotových úkolů: {}",
+        Message::MaintenanceDone => "Údržba dokončena",
+        Message::SizeBefore => "velikost před: {}",
+        Message::SizeAfter => "velikost po: {}",
+        Message::Bytes => "{} bajtů",
+        Message::UnknownSize => "neznámá",
+        Message::ListHeader => "Výpis všech úkolů",
+        Message::PendingListHeader => "Výpis nehotových úkolů",
+        Message::Source => "Profil {}, databáze {}",
+        Message::GroupPending => "Čeká ({})",
+        Message::GroupDone => "Hotovo ({})",
+        Message::BlockedBy => "blokováno úkoly {}",
+        Message::Progress => "hotovo {}/{}",
+        Message::NextPage => "Další stránka: --after-id {}",
+        Message::Skipped => "Přeskočeno {}: {}",
+        _ => return None,
+    })
+}
+
+// texts of the chosen language, passed to the rendering so that nothing else formats output
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Messages {
+    pub lang: Lang,
+}
+
+impl Messages {
+    pub fn new(lang: Lang) -> Self {
+        Self { lang }
+    }
+
+    /// the template, English where the language lacks it
+    pub fn get(self, message: Message) -> &'static str {
+        let translated = match self.lang {
+            Lang::English => None,
+            Lang::Czech => czech(message),
+        };
+        translated.unwrap_or_else(|| english(message))
+    }
+
+    /// the template with its `{}` replaced by the arguments in order
+    pub fn format(self, message: Message, args: &[&dyn Display]) -> String {
+        let mut parts = self.get(message).split("{}");
+        let mut text = String::from(parts.next().unwrap_or_default());
+        for (i, part) in parts.enumerate() {
+            if let Some(arg) = args.get(i) {
+                text.push_str(&arg.to_string());
+            }
+            text.push_str(part);
+        }
+        text
+    }
+}
+
+// what a command got turned down for, the backends return these so that main words them in the chosen language
+#[derive(Debug, Clone, PartialEq)]
+pub enum Refusal {
+    InvalidId(String),
+    NotANumber(String),
+    MergeIntoItself(i64),
+    AlreadyTracked(i64),
+    // the prefix and a line per todo it could mean
+    AmbiguousId(String, Vec<String>),
+}
+
+impl Refusal {
+    pub fn error(self) -> anyhow::Error {
+        anyhow::Error::new(self)
+    }
+
+    /// the exit code it ends the program with, see exit::HELP
+    pub fn failure(&self) -> Failure {
+        match self {
+            Refusal::InvalidId(_) => Failure::NotFound,
+            Refusal::NotANumber(_) | Refusal::MergeIntoItself(_) | Refusal::AmbiguousId(..) => Failure::Usage,
+            Refusal::AlreadyTracked(_) => Failure::Conflict,
+        }
+    }
+
+    pub fn text(&self, messages: Messages) -> String {
+        match self {
+            Refusal::InvalidId(id) => messages.format(Message::InvalidId, &[id]),
+            Refusal::NotANumber(id) => messages.format(Message::NotANumber, &[id]),
+            Refusal::MergeIntoItself(id) => messages.format(Message::MergeIntoItself, &[id]),
+            Refusal::AlreadyTracked(id) => messages.format(Message::AlreadyTracked, &[id]),
+            Refusal::AmbiguousId(prefix, candidates) => {
+                messages.format(Message::AmbiguousId, &[prefix, &candidates.join("\n")])
+            }
+        }
+    }
+}
+
+impl Display for Refusal {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(&self.text(Messages::default()))
+    }
+}
+
+impl std::error::Error for Refusal {}
+
+/// the error in the chosen language if it is a refusal, any other error is left as it is
+pub fn localize(err: anyhow::Error, messages: Messages) -> anyhow::Error {
+    match err.downcast_ref::<Refusal>() {
+        Some(refusal) => refusal.failure().error(refusal.text(messages)),
+        None => err,
+    }
+}
+
+/*-----------------------------------*/
+/*               tests               */
+/*-----------------------------------*/
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect() {
+        let env = |lang: &str| [None, Some(String::new()), Some(lang.to_string())];
+        assert_eq!(Lang::detect(None, env("cs_CZ.UTF-8")), Lang::Czech);
+        assert_eq!(Lang::detect(None, env("en_US.UTF-8")), Lang::English);
+        assert_eq!(Lang::detect(None, [None, None, None]), Lang::English);
+        // the flag wins
+        assert_eq!(Lang::detect(Some("cs"), env("en_US.UTF-8")), Lang::Czech);
+        assert_eq!(Lang::detect(Some("en"), env("cs_CZ.UTF-8")), Lang::English);
+        // unknown languages fall back instead of failing
+        assert_eq!(Lang::detect(Some("tlh"), env("cs_CZ.UTF-8")), Lang::English);
+        assert_eq!(Lang::detect(None, env("C")), Lang::English);
+        assert_eq!(Lang::detect(None, env("POSIX")), Lang::English);
+    }
+
+    #[test]
+    fn test_format() {
+        let english = Messages::default();
+        let czech = Messages::new(Lang::Czech);
+        assert_eq!(english.format(Message::Merging, &[&2, &1]), "Merging todo 2 into todo 1");
+        assert_eq!(czech.format(Message::Merging, &[&2, &1]), "Slučuji úkol 2 do úkolu 1");
+        assert_eq!(czech.format(Message::Clearing, &[]), "Mažu úkoly");
+        // missing translations are English
+        assert_eq!(czech.format(Message::Rows, &[&3]), "rows: 3");
+    }
+
+    #[test]
+    fn test_refusal() {
+        let czech = Messages::new(Lang::Czech);
+        let err = Refusal::InvalidId(String::from("99")).error();
+        assert_eq!(err.to_string(), "Invalid id 99");
+        let localized = localize(err, czech);
+        assert_eq!(localized.to_string(), "Neplatné id 99");
+        // translated, it still ends with its own code
+        assert_eq!(crate::exit::failure(&localized), Failure::NotFound);
+        let ambiguous = Refusal::AmbiguousId(String::from("1"), vec![String::from("- 10: a"), String::from("- 11: b")]);
+        assert_eq!(ambiguous.to_string(), "Id prefix 1 is ambiguous, candidates:\n- 10: a\n- 11: b");
+        // anything else keeps its text and its chain
+        let other = anyhow::anyhow!("Connection refused");
+        assert_eq!(localize(other, czech).to_string(), "Connection refused");
+    }
+
+    #[test]
+    fn test_templates() {
+        let all = [
+            Message::Adding,
+            Message::Added,
+            Message::Marking,
+            Message::MarkedDone,
+            Message::AlreadyDone,
+            Message::UndoHint,
+            Message::Reopened,
+            Message::CompletedMatching,
+            Message::NoneMatching,
+            Message::InvalidId,
+            Message::NotANumber,
+            Message::MergeIntoItself,
+            Message::AlreadyTracked,
+            Message::AmbiguousId,
+            Message::Pinned,
+            Message::Unpinned,
+            Message::Moved,
+            Message::Estimated,
+            Message::Edited,
+            Message::Clearing,
+            Message::Cleared,
+            Message::Opening,
+            Message::Merging,
+            Message::Merged,
+            Message::Summary,
+            Message::Workload,
+            Message::Tenant,
+            Message::NoTenants,
+            Message::PendingSummary,
+            Message::WorkloadUnestimated,
+            Message::CurrentStreak,
+            Message::LongestStreak,
+            Message::Weeks,
+            Message::ShowTodo,
+            Message::Status,
+            Message::StatusDone,
+            Message::StatusPending,
+            Message::Tracked,
+            Message::Estimate,
+            Message::StartedTracking,
+            Message::StoppedTracking,
+            Message::NotTracking,
+            Message::WouldPurge,
+            Message::Purged,
+            Message::MaintenanceDone,
+            Message::SizeBefore,
+            Message::SizeAfter,
+            Message::Bytes,
+            Message::UnknownSize,
+            Message::Ping,
+            Message::SchemaVersion,
+            Message::NoMigrations,
+            Message::Schema,
+            Message::SchemaAdded,
+            Message::SchemaAsExpected,
+            Message::Rows,
+            Message::Size,
+            Message::Ssl,
+            Message::Password,
+            Message::Profile,
+            Message::Url,
+            Message::Intact,
+            Message::IntegrityCheck,
+            Message::Salvaged,
+            Message::Backend,
+            Message::Statement,
+            Message::Plan,
+            Message::ListHeader,
+            Message::PendingListHeader,
+            Message::Source,
+            Message::GroupPending,
+            Message::GroupDone,
+            Message::BlockedBy,
+            Message::Progress,
+            Message::NextPage,
+            Message::Skipped,
+        ];
+        // a translation needs the same placeholders, or arguments would go missing
+        for message in all {
+            if let Some(translated) = czech(message) {
+                assert_eq!(
+                    translated.matches("{}").count(),
+                    english(message).matches("{}").count(),
+                    "{message:?}"
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,223 @@
+// manual order of the todos for the move command, positions are floats so that a
+// todo can go between two others without touching the rest until the gaps run out
+
+// neighbours closer than this are renumbered before anything goes between them,
+// far above the precision of a double around the positions renumbering produces,
+// new todos are at their creation time in seconds where a double is coarser,
+// so the midpoint also has to differ from both of them
+pub const MIN_GAP: f64 = 1e-9;
+
+// how far below the last todo the bottom is, a microsecond so that todos added
+// right after a move still go below the moved one
+pub const BOTTOM_STEP: f64 = 1e-6;
+
+// where move puts the todo
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Placement {
+    Before(i64),
+    After(i64),
+    Top,
+    Bottom,
+}
+
+/// position for a todo placed as asked, `order` is every other todo as (id, position)
+/// sorted by position, None when the neighbours are too close and `renumber` is due first,
+/// as well when a position is so far out, like that of a huge imported id, that a double
+/// can't tell it from the next one, an error when the todo to place it next to isn't in `order`
+pub fn position_for(order: &[(i64, f64)], placement: Placement) -> anyhow::Result<Option<f64>> {
+    let index_of = |id: i64| {
+        order
+            .iter()
+            .position(|(other, _)| *other == id)
+            .ok_or_else(|| anyhow::anyhow!("Todo {id} is not among the todos to move next to"))
+    };
+    let (lower, upper) = match placement {
+        Placement::Top => (None, order.first()),
+        Placement::Bottom => (order.last(), None),
+        Placement::Before(id) => {
+            let i = index_of(id)?;
+            (i.checked_sub(1).map(|i| &order[i]), Some(&order[i]))
+        }
+        Placement::After(id) => {
+            let i = index_of(id)?;
+            (Some(&order[i]), order.get(i + 1))
+        }
+    };
+    // a comparison with NaN is false, so such positions are renumbered as well
+    Ok(match (lower, upper) {
+        (None, None) => Some(1.0),
+        (None, Some((_, upper))) => Some(upper - 1.0).filter(|top| top < upper),
+        (Some((_, lower)), None) => Some(lower + BOTTOM_STEP).filter(|bottom| bottom > lower && bottom.is_finite()),
+        (Some((_, lower)), Some((_, upper))) => {
+            let middle = (lower + upper) / 2.0;
+            (upper - lower >= 2.0 * MIN_GAP && *lower < middle && middle < *upper).then_some(middle)
+        }
+    })
+}
+
+/// positions 1, 2, 3 and so on in the same order, for a `order` sorted by position
+pub fn renumber(order: &[(i64, f64)]) -> Vec<(i64, f64)> {
+    order.iter().enumerate().map(|(i, (id, _))| (*id, (i + 1) as f64)).collect()
+}
+
+// what move writes
+#[derive(Debug, PartialEq)]
+pub struct Move {
+    /// the positions to renumber every todo to first, empty while there is room
+    pub renumbered: Vec<(i64, f64)>,
+    /// None when there is no room even after renumbering
+    pub position: Option<f64>,
+}
+
+/// the move of `id`, with `order` every todo sorted by position
+pub fn plan_move(order: &[(i64, f64)], id: i64, placement: Placement) -> anyhow::Result<Move> {
+    let others = |order: &[(i64, f64)]| order.iter().copied().filter(|(other, _)| *other != id).collect::<Vec<_>>();
+    if let Some(position) = position_for(&others(order), placement)? {
+        return Ok(Move {
+            renumbered: vec![],
+            position: Some(position),
+        });
+    }
+    let renumbered = renumber(order);
+    let position = position_for(&others(&renumbered), placement)?;
+    Ok(Move { renumbered, position })
+}
+
+/*-----------------------------------*/
+/*               tests               */
+/*-----------------------------------*/
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `order` with `id` placed, renumbering when there is no room, like the move command
+    fn place(order: &mut Vec<(i64, f64)>, id: i64, placement: Placement) -> bool {
+        let Move { renumbered, position } = plan_move(order, id, placement).unwrap();
+        let was_renumbered = !renumbered.is_empty();
+        if was_renumbered {
+            *order = renumbered;
+        }
+        order.retain(|(other, _)| *other != id);
+        order.push((id, position.unwrap()));
+        order.sort_by(|a, b| a.1.total_cmp(&b.1));
+        was_renumbered
+    }
+
+    fn ids(order: &[(i64, f64)]) -> Vec<i64> {
+        order.iter().map(|(id, _)| *id).collect()
+    }
+
+    #[test]
+    fn test_position_for() {
+        let order = [(1, 1.0), (2, 2.0), (3, 3.0)];
+        assert_eq!(position_for(&order, Placement::Top).unwrap(), Some(0.0));
+        assert_eq!(position_for(&order, Placement::Bottom).unwrap(), Some(3.0 + BOTTOM_STEP));
+        assert_eq!(position_for(&order, Placement::Before(2)).unwrap(), Some(1.5));
+        assert_eq!(position_for(&order, Placement::After(2)).unwrap(), Some(2.5));
+        assert_eq!(position_for(&order, Placement::Before(1)).unwrap(), Some(0.0));
+        assert_eq!(position_for(&order, Placement::After(3)).unwrap(), Some(3.0 + BOTTOM_STEP));
+        assert_eq!(position_for(&[], Placement::Top).unwrap(), Some(1.0));
+        assert!(position_for(&order, Placement::After(7)).is_err());
+        // no room left between them
+        assert_eq!(position_for(&[(1, 1.0), (2, 1.0 + MIN_GAP)], Placement::After(1)).unwrap(), None);
+        let created = 1_760_000_000.25_f64;
+        let next = f64::from_bits(created.to_bits() + 1);
+        assert_eq!(position_for(&[(1, created), (2, next)], Placement::After(1)).unwrap(), None);
+        assert_eq!(renumber(&[(5, -3.0), (2, 0.25), (9, 0.5)]), vec![(5, 1.0), (2, 2.0), (9, 3.0)]);
+
+        // a position of i64::MAX as a double, adding a microsecond doesn't change it
+        let huge = i64::MAX as f64;
+        assert_eq!(position_for(&[(1, 1.0), (2, huge)], Placement::Bottom).unwrap(), None);
+        assert_eq!(position_for(&[(1, -huge)], Placement::Top).unwrap(), None);
+        assert_eq!(position_for(&[(1, f64::MAX)], Placement::Bottom).unwrap(), None);
+        assert_eq!(position_for(&[(1, f64::NAN)], Placement::Top).unwrap(), None);
+        assert_eq!(position_for(&[(1, f64::INFINITY)], Placement::Bottom).unwrap(), None);
+        assert_eq!(position_for(&[(1, -f64::MAX), (2, f64::MAX)], Placement::After(1)).unwrap(), Some(0.0));
+    }
+
+    proptest::proptest! {
+        // whatever the database holds, a position is between the neighbours or renumbering is due
+        #[test]
+        fn test_position_for_any_order(
+            mut positions in proptest::collection::vec(proptest::num::f64::ANY, 0..8),
+            placement in 0..4u8,
+            next_to in 0..8usize,
+        ) {
+            positions.sort_by(f64::total_cmp);
+            let order: Vec<(i64, f64)> = positions.iter().enumerate().map(|(i, p)| (i as i64 + 1, *p)).collect();
+            let other = next_to as i64 + 1;
+            let placement = match placement {
+                0 => Placement::Top,
+                1 => Placement::Bottom,
+                2 => Placement::Before(other),
+                _ => Placement::After(other),
+            };
+            let (lower, upper) = match placement {
+                Placement::Top => (None, order.first()),
+                Placement::Bottom => (order.last(), None),
+                Placement::Before(id) | Placement::After(id) if id as usize > order.len() => {
+                    proptest::prop_assert!(position_for(&order, placement).is_err());
+                    return Ok(());
+                }
+                Placement::Before(id) => ((id as usize).checked_sub(2).and_then(|i| order.get(i)), order.get(id as usize - 1)),
+                Placement::After(id) => (order.get(id as usize - 1), order.get(id as usize)),
+            };
+            if let Some(position) = position_for(&order, placement).unwrap() {
+                proptest::prop_assert!(position.is_finite());
+                proptest::prop_assert!(lower.is_none_or(|(_, lower)| *lower < position));
+                proptest::prop_assert!(upper.is_none_or(|(_, upper)| position < *upper));
+            }
+            // and after renumbering there always is room
+            proptest::prop_assert!(position_for(&renumber(&order), placement).unwrap().is_some());
+        }
+    }
+
+    #[test]
+    fn test_placement_is_respected() {
+        // always squeezing in right after the first todo halves the gap every time
+        let mut order: Vec<(i64, f64)> = (1..=5).map(|id| (id, id as f64)).collect();
+        let mut renumbered = 0;
+        for _ in 0..100 {
+            renumbered += usize::from(place(&mut order, 3, Placement::After(1)));
+            assert_eq!(ids(&order), vec![1, 3, 2, 4, 5]);
+            renumbered += usize::from(place(&mut order, 2, Placement::After(1)));
+            assert_eq!(ids(&order), vec![1, 2, 3, 4, 5]);
+        }
+        assert!(renumbered > 0);
+
+        // pseudo random moves, every one ends up where it was asked to
+        let mut seed: u64 = 42;
+        let mut next = |below: u64| {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (seed >> 33) % below
+        };
+        let mut order: Vec<(i64, f64)> = (1..=20).map(|id| (id, id as f64)).collect();
+        for _ in 0..2000 {
+            let id = next(20) as i64 + 1;
+            let other = loop {
+                let other = next(20) as i64 + 1;
+                if other != id {
+                    break other;
+                }
+            };
+            let placement = match next(4) {
+                0 => Placement::Top,
+                1 => Placement::Bottom,
+                2 => Placement::Before(other),
+                _ => Placement::After(other),
+            };
+            let before: Vec<i64> = ids(&order).into_iter().filter(|other| *other != id).collect();
+            place(&mut order, id, placement);
+            let after = ids(&order);
+            let i = after.iter().position(|other| *other == id).unwrap();
+            match placement {
+                Placement::Top => assert_eq!(i, 0),
+                Placement::Bottom => assert_eq!(i, after.len() - 1),
+                Placement::Before(other) => assert_eq!(after[i + 1], other),
+                Placement::After(other) => assert_eq!(after[i - 1], other),
+            }
+            // the others keep their order, renumbered or not
+            assert_eq!(after.into_iter().filter(|other| *other != id).collect::<Vec<_>>(), before);
+        }
+    }
+}
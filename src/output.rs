@@ -0,0 +1,186 @@
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+// where the rendered output goes, stdout or a file replaced only once everything is written
+pub enum Output {
+    Stdout(std::io::Stdout),
+    File(AtomicFile),
+}
+
+impl Output {
+    /// `-` and no path at all are stdout
+    pub fn open(path: Option<&Path>) -> anyhow::Result<Self> {
+        match path {
+            Some(path) if path != Path::new("-") => Ok(Self::File(AtomicFile::create(path)?)),
+            _ => Ok(Self::Stdout(std::io::stdout())),
+        }
+    }
+
+    /// move the file into place, until then an existing file keeps its old contents
+    pub fn finish(self) -> anyhow::Result<()> {
+        match self {
+            Self::Stdout(mut stdout) => Ok(stdout.flush()?),
+            Self::File(file) => file.commit(),
+        }
+    }
+}
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            Self::Stdout(stdout) => stdout.write(buf),
+            Self::File(file) => file.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            Self::Stdout(stdout) => stdout.flush(),
+            Self::File(file) => file.flush(),
+        }
+    }
+}
+
+// temporary file next to the destination, renamed over it by commit and removed when dropped before
+pub struct AtomicFile {
+    path: PathBuf,
+    temp: PathBuf,
+    file: Option<BufWriter<File>>,
+}
+
+impl AtomicFile {
+    pub fn create(path: &Path) -> anyhow::Result<Self> {
+        let name = path
+            .file_name()
+            .ok_or_else(|| anyhow::anyhow!("Cannot write {}, it is not a file name", path.display()))?;
+        // the same directory, a rename across file systems wouldn't be atomic
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        let temp = dir.join(format!(".{}.{}.tmp", name.to_string_lossy(), std::process::id()));
+
+        let file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&temp)
+            .map_err(|err| match err.kind() {
+                ErrorKind::NotFound => anyhow::anyhow!(
+                    "Cannot write {}, the directory {} doesn't exist",
+                    path.display(),
+                    dir.display()
+                ),
+                ErrorKind::PermissionDenied => anyhow::anyhow!(
+                    "Cannot write {}, no permission to create files in {}",
+                    path.display(),
+                    dir.display()
+                ),
+                _ => anyhow::anyhow!("Cannot write {}: {err}", path.display()),
+            })?;
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            temp,
+            file: Some(BufWriter::new(file)),
+        })
+    }
+
+    pub fn commit(mut self) -> anyhow::Result<()> {
+        let file = self.file.take().expect("file is only taken by commit");
+        let error = |err: std::io::Error| anyhow::anyhow!("Cannot write {}: {err}", self.path.display());
+
+        let file = file.into_inner().map_err(|err| error(err.into_error()))?;
+        file.sync_all().map_err(error)?;
+        drop(file);
+        std::fs::rename(&self.temp, &self.path).map_err(|err| match err.kind() {
+            ErrorKind::PermissionDenied => anyhow::anyhow!("Cannot replace {}, permission denied", self.path.display()),
+            _ => error(err),
+        })
+    }
+}
+
+impl Write for AtomicFile {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.file.as_mut().expect("file is only taken by commit").write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.file.as_mut().expect("file is only taken by commit").flush()
+    }
+}
+
+impl Drop for AtomicFile {
+    fn drop(&mut self) {
+        // committed or not, the temporary file mustn't stay behind
+        self.file.take();
+        let _ = std::fs::remove_file(&self.temp);
+    }
+}
+
+/*-----------------------------------*/
+/*               tests               */
+/*-----------------------------------*/
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries(dir: &Path) -> Vec<String> {
+        let mut names: Vec<String> = std::fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn test_replace() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("todos.json");
+        std::fs::write(&path, "old").unwrap();
+
+        let mut output = Output::open(Some(&path)).unwrap();
+        write!(output, "new").unwrap();
+        // nothing is visible before the end
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "old");
+        output.finish().unwrap();
+
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "new");
+        assert_eq!(entries(dir.path()), vec!["todos.json"]);
+    }
+
+    #[test]
+    fn test_failure_keeps_original() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("todos.json");
+        std::fs::write(&path, "old").unwrap();
+
+        {
+            let mut output = Output::open(Some(&path)).unwrap();
+            write!(output, "half of it").unwrap();
+            // an error in between drops the output without finishing it
+        }
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "old");
+        assert_eq!(entries(dir.path()), vec!["todos.json"]);
+    }
+
+    #[test]
+    fn test_stdout_and_errors() {
+        assert!(matches!(Output::open(None).unwrap(), Output::Stdout(_)));
+        assert!(matches!(Output::open(Some(Path::new("-"))).unwrap(), Output::Stdout(_)));
+
+        let dir = tempfile::tempdir().unwrap();
+        let missing = dir.path().join("missing").join("todos.json");
+        let err = Output::open(Some(&missing)).err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "Cannot write {}, the directory {} doesn't exist",
+                missing.display(),
+                missing.parent().unwrap().display()
+            )
+        );
+        assert!(Output::open(Some(Path::new("/"))).is_err());
+    }
+}
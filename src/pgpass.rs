@@ -0,0 +1,150 @@
+use std::path::{Path, PathBuf};
+
+/// the password file libpq would read, $PGPASSFILE or ~/.pgpass
+pub fn default_path() -> Option<PathBuf> {
+    if let Some(path) = std::env::var_os("PGPASSFILE") {
+        return Some(PathBuf::from(path));
+    }
+    std::env::var_os("HOME").map(|home| Path::new(&home).join(".pgpass"))
+}
+
+/// password of the first line of the file matching the connection, if the file is readable
+pub fn lookup(path: &Path, host: &str, port: u16, database: &str, user: &str) -> Option<String> {
+    let contents = std::fs::read_to_string(path).ok()?;
+    if !is_private(path) {
+        // same as libpq, a password everybody can read isn't used
+        eprintln!(
+            "Ignoring {}, it has to be readable only by its owner (chmod 0600)",
+            path.display()
+        );
+        return None;
+    }
+    find_password(&contents, host, port, database, user)
+}
+
+#[cfg(unix)]
+fn is_private(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::metadata(path).is_ok_and(|metadata| metadata.permissions().mode() & 0o077 == 0)
+}
+
+#[cfg(not(unix))]
+fn is_private(_path: &Path) -> bool {
+    true
+}
+
+/// lines are `host:port:database:user:password`, each of the first four may be `*`
+pub fn find_password(contents: &str, host: &str, port: u16, database: &str, user: &str) -> Option<String> {
+    let port = port.to_string();
+    contents
+        .lines()
+        .filter(|line| !line.trim_start().starts_with('#'))
+        .filter_map(split_line)
+        .find(|fields| {
+            [host, port.as_str(), database, user]
+                .iter()
+                .zip(fields.iter())
+                .all(|(wanted, field)| field == "*" || field == wanted)
+        })
+        .map(|mut fields| fields.swap_remove(4))
+}
+
+/// the five fields of a line, `\:` and `\\` being a literal colon and backslash
+fn split_line(line: &str) -> Option<Vec<String>> {
+    let line = line.strip_suffix('\r').unwrap_or(line);
+    let mut fields = vec![String::new()];
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            // a dangling backslash at the end of the line is kept
+            '\\' => fields.last_mut()?.push(chars.next().unwrap_or('\\')),
+            ':' if fields.len() < 5 => fields.push(String::new()),
+            c => fields.last_mut()?.push(c),
+        }
+    }
+
+    (fields.len() == 5).then_some(fields)
+}
+
+/*-----------------------------------*/
+/*               tests               */
+/*-----------------------------------*/
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn find(contents: &str, host: &str, database: &str) -> Option<String> {
+        find_password(contents, host, 5432, database, "postgres")
+    }
+
+    #[test]
+    fn test_exact_match() {
+        let contents = "localhost:5432:todos:postgres:secret\n";
+        assert_eq!(find(contents, "localhost", "todos"), Some(String::from("secret")));
+        assert_eq!(find(contents, "localhost", "other"), None);
+        assert_eq!(find(contents, "db.example.com", "todos"), None);
+        assert_eq!(find_password(contents, "localhost", 5433, "todos", "postgres"), None);
+        assert_eq!(find_password(contents, "localhost", 5432, "todos", "admin"), None);
+    }
+
+    #[test]
+    fn test_wildcards_and_order() {
+        let contents = "\
+            # comments and broken lines are skipped\n\
+            localhost:5432\n\
+            *:*:todos:postgres:first\n\
+            *:*:*:*:fallback\n\
+            localhost:5432:todos:postgres:too late\n";
+        assert_eq!(find(contents, "localhost", "todos"), Some(String::from("first")));
+        assert_eq!(find(contents, "db.example.com", "todos"), Some(String::from("first")));
+        assert_eq!(find(contents, "localhost", "other"), Some(String::from("fallback")));
+        assert_eq!(find("", "localhost", "todos"), None);
+    }
+
+    #[test]
+    fn test_escapes() {
+        // the password may contain colons, escaped or not
+        let contents = "local\\:host:5432:to\\\\dos:postgres:pa\\:ss:word\r\n";
+        assert_eq!(find(contents, "local:host", "to\\dos"), Some(String::from("pa:ss:word")));
+        assert_eq!(find(contents, "local", "to\\dos"), None);
+        assert_eq!(find("*:*:*:*:trailing\\", "localhost", "todos"), Some(String::from("trailing\\")));
+        assert_eq!(find("*:*:*:*:", "localhost", "todos"), Some(String::new()));
+    }
+
+    #[test]
+    fn test_lookup_private_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("pgpass");
+        std::fs::write(&path, "*:*:todos:*:secret\n").unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+            assert_eq!(lookup(&path, "localhost", 5432, "todos", "postgres"), None);
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600)).unwrap();
+        }
+        assert_eq!(
+            lookup(&path, "localhost", 5432, "todos", "postgres"),
+            Some(String::from("secret"))
+        );
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(lookup(&path, "localhost", 5432, "todos", "postgres"), None);
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn test_split_line_any_text(line in "\\PC*") {
+            if let Some(fields) = split_line(&line) {
+                proptest::prop_assert_eq!(fields.len(), 5);
+            }
+        }
+
+        // fields escaped like psql expects them come back as they were
+        #[test]
+        fn test_split_line_escaped(fields in proptest::collection::vec("[^\r\n]*", 5)) {
+            let line: Vec<String> = fields.iter().map(|field| field.replace('\\', "\\\\").replace(':', "\\:")).collect();
+            proptest::prop_assert_eq!(split_line(&line.join(":")), Some(fields));
+        }
+    }
+}
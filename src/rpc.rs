@@ -0,0 +1,345 @@
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::io::{BufRead, Write};
+
+use crate::{handle_command_in_time, hooks, ids, notify_hook, notify_watchers, Args, Command, DBTrait, Handled, ListArgs};
+
+// error codes of the JSON-RPC 2.0 specification
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+// the command itself failed, like an ambiguous id
+const COMMAND_FAILED: i64 = -32000;
+
+#[derive(Debug, Deserialize)]
+struct Request {
+    jsonrpc: Option<String>,
+    method: String,
+    #[serde(default)]
+    params: Value,
+    /// None for notifications, they get no response
+    id: Option<Value>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct Params {
+    id: Option<Value>,
+    description: Option<String>,
+    after: Vec<Value>,
+    force: bool,
+    pending: bool,
+    pinned: bool,
+}
+
+fn error(id: Value, code: i64, message: impl Into<String>) -> Value {
+    json!({"jsonrpc": "2.0", "error": {"code": code, "message": message.into()}, "id": id})
+}
+
+/// ids may be sent as numbers or as the strings the command line takes
+fn id_text(id: &Value) -> Result<String, String> {
+    match id {
+        Value::Number(number) => match number.as_i64() {
+            Some(id) if id >= 0 => Ok(id.to_string()),
+            // a whole number beyond i64 would be an id if there were room for it
+            _ if number.as_u64().is_some() || number.as_f64().is_some_and(|n| n.fract() == 0.0 && n > i64::MAX as f64) => {
+                Err(ids::out_of_range(&number.to_string()))
+            }
+            _ => Err(format!("Invalid id {number}, expected a non-negative integer")),
+        },
+        Value::String(text) => Ok(text.clone()),
+        _ => Err(format!("Invalid id {id}, expected a number or a string")),
+    }
+}
+
+/// the command a method stands for, Ok(None) for shutdown
+fn command(method: &str, params: Value) -> Result<Option<Command>, (i64, String)> {
+    let invalid = |message: String| (INVALID_PARAMS, message);
+    let params: Params = match params {
+        Value::Null => Params::default(),
+        params => serde_json::from_value(params).map_err(|err| invalid(err.to_string()))?,
+    };
+    let id = || {
+        let id = params.id.as_ref().ok_or_else(|| invalid(String::from("Missing id")))?;
+        id_text(id).map_err(invalid).map(Some)
+    };
+
+    let command = match method {
+        "add" => Command::Add {
+            description: params.description.clone().ok_or_else(|| invalid(String::from("Missing description")))?,
+            // the defaults of the command line
+            max_length: 10240,
+            truncate: false,
+            after: params.after.iter().map(id_text).collect::<Result<_, _>>().map_err(invalid)?,
+            meta: vec![],
+            done: false,
+            created_at: None,
+            completed_at: None,
+            estimate: None,
+        },
+        "done" => Command::Done {
+            id: id()?,
+            force: params.force,
+            // already_done in the result tells, there is no exit code to set
+            strict: false,
+            stdin: false,
+            matching: None,
+            yes: false,
+            max: 50,
+        },
+        "pin" => Command::Pin { id: id()?, stdin: false },
+        "unpin" => Command::Unpin { id: id()?, stdin: false },
+        "show" => Command::Show {
+            id: id()?.unwrap_or_default(),
+        },
+        "list" => Command::List(ListArgs {
+            pending: params.pending,
+            pinned: params.pinned,
+            ..Default::default()
+        }),
+        // there is nobody to ask, calling clear is the decision
+        "clear" => Command::Clear { yes: true },
+        "stats" => Command::Stats { by_day: false, days: 14 },
+        "shutdown" => return Ok(None),
+        _ => return Err((METHOD_NOT_FOUND, format!("Method not found: {method}"))),
+    };
+    Ok(Some(command))
+}
+
+/// answer one request, None for notifications, the flag tells whether to shut down
+async fn respond(
+    args: &Args,
+    database: &impl DBTrait,
+    hook: Option<&hooks::Hook>,
+    request: Value,
+) -> (Option<Value>, bool) {
+    let request: Request = match serde_json::from_value(request) {
+        Ok(request) => request,
+        Err(err) => return (Some(error(Value::Null, INVALID_REQUEST, err.to_string())), false),
+    };
+    let id = request.id.clone().unwrap_or(Value::Null);
+    if request.jsonrpc.as_deref() != Some("2.0") {
+        return (Some(error(id, INVALID_REQUEST, "jsonrpc has to be \"2.0\"")), false);
+    }
+
+    let (response, shutdown) = match command(&request.method, request.params) {
+        Ok(None) => (json!({"jsonrpc": "2.0", "result": null, "id": id}), true),
+        Ok(Some(command)) => {
+            let args = Args {
+                cmd: Some(command),
+                ..args.clone()
+            };
+            match handle_command_in_time(&args, database).await {
+                Ok(Handled { outcome, .. }) => {
+                    notify_watchers(&args, database).await;
+                    notify_hook(hook, &outcome, database).await;
+                    (json!({"jsonrpc": "2.0", "result": outcome, "id": id}), false)
+                }
+                Err(err) => (error(id, COMMAND_FAILED, err.to_string()), false),
+            }
+        }
+        Err((code, message)) => (error(id, code, message), false),
+    };
+    (request.id.is_some().then_some(response), shutdown)
+}
+
+/// read newline delimited requests until shutdown or the end of the input, on one open database
+pub async fn serve(
+    args: &Args,
+    database: &impl DBTrait,
+    hook: Option<&hooks::Hook>,
+    input: impl BufRead,
+    mut output: impl Write,
+) -> anyhow::Result<()> {
+    for line in input.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let mut shutdown = false;
+        let response = match serde_json::from_str::<Value>(&line) {
+            Err(err) => Some(error(Value::Null, PARSE_ERROR, err.to_string())),
+            Ok(Value::Array(requests)) if requests.is_empty() => {
+                Some(error(Value::Null, INVALID_REQUEST, "Empty batch"))
+            }
+            // a batch is answered as a whole, shutting down only after all of it
+            Ok(Value::Array(requests)) => {
+                let mut responses = Vec::with_capacity(requests.len());
+                for request in requests {
+                    let (response, stop) = respond(args, database, hook, request).await;
+                    responses.extend(response);
+                    shutdown |= stop;
+                }
+                (!responses.is_empty()).then_some(Value::Array(responses))
+            }
+            Ok(request) => {
+                let (response, stop) = respond(args, database, hook, request).await;
+                shutdown = stop;
+                response
+            }
+        };
+
+        if let Some(response) = response {
+            writeln!(output, "{response}")?;
+            // the editor waits for every answer
+            output.flush()?;
+        }
+        if shutdown {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/*-----------------------------------*/
+/*               tests               */
+/*-----------------------------------*/
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MeteredDB, SqliteDBStruct, TableName, SLOW_CALL_THRESHOLD};
+
+    async fn session(input: &str) -> Vec<Value> {
+        let pool = sqlx::sqlite::SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        let db = SqliteDBStruct::new(pool, TableName::default());
+        let mut output = Vec::new();
+        serve(&Args::default(), &db, None, input.as_bytes(), &mut output).await.unwrap();
+        String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_session() {
+        let responses = session(concat!(
+            r#"{"jsonrpc":"2.0","method":"add","params":{"description":"buy milk"},"id":1}"#,
+            "\n",
+            r#"{"jsonrpc":"2.0","method":"done","params":{"id":1},"id":"two"}"#,
+            "\n\n",
+            r#"{"jsonrpc":"2.0","method":"list","id":3}"#,
+            "\n",
+        ))
+        .await;
+        assert_eq!(
+            responses,
+            vec![
+                json!({"jsonrpc": "2.0", "result": {"outcome": "added", "result": {"id": 1, "description": "buy milk"}}, "id": 1}),
+                json!({"jsonrpc": "2.0", "result": {"outcome": "completed", "result": {"requested": "1", "found": 1}}, "id": "two"}),
+                json!({"jsonrpc": "2.0", "result": {"outcome": "listing", "result": {
+                    "todos": [{"id": 1, "description": "buy milk", "done": true}],
+                    "tracked_secs": null,
+                }}, "id": 3}),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_schema_checked_once() {
+        use sqlx::Executor;
+        let pool = sqlx::sqlite::SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        let db = MeteredDB::new(SqliteDBStruct::new(pool, TableName::default()), SLOW_CALL_THRESHOLD, false);
+        let list = concat!(r#"{"jsonrpc":"2.0","method":"list","id":1}"#, "\n");
+        serve(&Args::default(), &db, None, list.as_bytes(), &mut Vec::new()).await.unwrap();
+
+        // create_table would add the index again if it looked
+        db.inner.sqlite_pool.execute("DROP INDEX todos_order").await.unwrap();
+        let mut output = Vec::new();
+        serve(&Args::default(), &db, None, list.repeat(100).as_bytes(), &mut output).await.unwrap();
+        let responses: Vec<Value> = String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(responses.len(), 100);
+        assert!(responses.iter().all(|response| response["result"]["outcome"] == "listing"));
+        let calls = db.calls();
+        let schema = |method| calls.iter().filter(|call| **call == method).count();
+        assert_eq!((schema("check_schema"), schema("create_table")), (1, 1), "{calls:?}");
+        let index = || {
+            sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM sqlite_master WHERE name = 'todos_order'")
+                .fetch_one(&*db.inner.sqlite_pool)
+        };
+        assert_eq!(index().await.unwrap(), 0);
+
+        db.invalidate_schema_cache();
+        db.create_table().await.unwrap();
+        assert_eq!(index().await.unwrap(), 1);
+    }
+
+    #[test]
+    fn test_id_text() {
+        assert_eq!(id_text(&json!(7)), Ok(String::from("7")));
+        assert_eq!(id_text(&json!(i64::MAX)), Ok(i64::MAX.to_string()));
+        assert_eq!(id_text(&json!("a3f")), Ok(String::from("a3f")));
+        assert_eq!(
+            id_text(&json!(u64::MAX)),
+            Err(String::from("Id 18446744073709551615 is out of range, the largest is 9223372036854775807"))
+        );
+        assert_eq!(
+            id_text(&json!(1e30)),
+            Err(String::from("Id 1e30 is out of range, the largest is 9223372036854775807"))
+        );
+        assert_eq!(id_text(&json!(-5)), Err(String::from("Invalid id -5, expected a non-negative integer")));
+        assert_eq!(id_text(&json!(1.5)), Err(String::from("Invalid id 1.5, expected a non-negative integer")));
+        for id in [json!(-1e30), json!(null), json!([1])] {
+            assert!(id_text(&id).is_err(), "{id}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_errors_keep_the_loop_going() {
+        let responses = session(concat!(
+            "{not json\n",
+            r#"{"jsonrpc":"2.0","method":"fly","id":1}"#,
+            "\n",
+            r#"{"jsonrpc":"2.0","method":"done","params":{"id":"abc"},"id":2}"#,
+            "\n",
+            r#"{"jsonrpc":"2.0","method":"add","params":{"title":"x"},"id":3}"#,
+            "\n",
+            r#"{"method":"stats","id":4}"#,
+            "\n",
+            // a notification is run but not answered
+            r#"{"jsonrpc":"2.0","method":"add","params":{"description":"quietly"}}"#,
+            "\n",
+            r#"{"jsonrpc":"2.0","method":"stats","id":5}"#,
+            "\n",
+        ))
+        .await;
+        let codes: Vec<&Value> = responses.iter().map(|response| &response["error"]["code"]).collect();
+        assert_eq!(codes, vec![&json!(-32700), &json!(-32601), &json!(-32000), &json!(-32602), &json!(-32600), &Value::Null]);
+        assert_eq!(responses[0]["id"], Value::Null);
+        assert_eq!(responses[5]["result"]["result"]["counts"], json!({"pending": 1, "done": 0, "workload_minutes": 0, "unestimated": 1}));
+    }
+
+    #[tokio::test]
+    async fn test_batch_and_shutdown() {
+        let responses = session(concat!(
+            r#"[{"jsonrpc":"2.0","method":"add","params":{"description":"a"},"id":1},"#,
+            r#"{"jsonrpc":"2.0","method":"shutdown","id":2},"#,
+            r#"{"jsonrpc":"2.0","method":"add","params":{"description":"b"},"id":3}]"#,
+            "\n",
+            r#"{"jsonrpc":"2.0","method":"add","params":{"description":"never"},"id":4}"#,
+            "\n",
+        ))
+        .await;
+        // the batch finishes, nothing after it runs
+        assert_eq!(responses.len(), 1);
+        let ids: Vec<&Value> = responses[0].as_array().unwrap().iter().map(|response| &response["id"]).collect();
+        assert_eq!(ids, vec![&json!(1), &json!(2), &json!(3)]);
+        assert_eq!(responses[0][1]["result"], Value::Null);
+
+        assert_eq!(session("[]\n").await[0]["error"]["code"], json!(-32600));
+    }
+}
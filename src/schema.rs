@@ -0,0 +1,234 @@
+// the tables, columns and indexes create_table makes, creating, upgrading and checking
+// the schema are all derived from this one description so that they can't disagree
+
+use crate::{Dialect, LISTING_ORDER};
+
+pub struct Column {
+    pub name: &'static str,
+    /// the definition on sqlite and on postgres, ADD COLUMN takes the same
+    pub sqlite: &'static str,
+    pub postgres: &'static str,
+    /// the names information_schema may give the postgres type, the first is the one made
+    pub postgres_types: &'static [&'static str],
+    /// whether tables of older versions lack it, create_table adds it to them
+    pub added: bool,
+}
+
+pub struct Table {
+    /// the placeholder TableName::sql expands
+    pub placeholder: &'static str,
+    /// the name is that of the todos table with this appended
+    pub suffix: &'static str,
+    pub columns: &'static [Column],
+    /// after the columns, like a primary key of several of them
+    pub constraint: Option<&'static str>,
+}
+
+pub struct Index {
+    pub placeholder: &'static str,
+    pub suffix: &'static str,
+    /// the indexed expressions on sqlite and on postgres, where an expression needs its own parentheses
+    pub sqlite: &'static str,
+    pub postgres: &'static str,
+}
+
+pub struct Schema {
+    /// the todos table first, the others refer to it
+    pub tables: &'static [Table],
+    pub indexes: &'static [Index],
+}
+
+const fn column(name: &'static str, sqlite: &'static str, postgres: &'static str, postgres_types: &'static [&'static str]) -> Column {
+    Column {
+        name,
+        sqlite,
+        postgres,
+        postgres_types,
+        added: false,
+    }
+}
+
+// in the order the columns were introduced
+static SCHEMA: Schema = Schema {
+    tables: &[
+        Table {
+            placeholder: "{todos}",
+            suffix: "",
+            columns: &[
+                column("id", "INTEGER PRIMARY KEY NOT NULL", "BIGSERIAL PRIMARY KEY", &["bigint"]),
+                column("description", "TEXT NOT NULL", "TEXT NOT NULL", &["text", "character varying"]),
+                column("done", "BOOLEAN NOT NULL DEFAULT 0", "BOOLEAN NOT NULL DEFAULT FALSE", &["boolean"]),
+                Column {
+                    added: true,
+                    ..column("completed_at", "TIMESTAMP", "TIMESTAMPTZ", &["timestamp with time zone"])
+                },
+                Column {
+                    added: true,
+                    ..column("created_at", "TIMESTAMP", "TIMESTAMPTZ", &["timestamp with time zone"])
+                },
+                Column {
+                    added: true,
+                    ..column("metadata", "TEXT CHECK (json_valid(metadata))", "JSONB", &["jsonb"])
+                },
+                Column {
+                    added: true,
+                    ..column("pinned", "BOOLEAN NOT NULL DEFAULT 0", "BOOLEAN NOT NULL DEFAULT FALSE", &["boolean"])
+                },
+                Column {
+                    added: true,
+                    ..column("position", "REAL", "DOUBLE PRECISION", &["double precision"])
+                },
+                Column {
+                    added: true,
+                    ..column("estimate_minutes", "INTEGER", "BIGINT", &["bigint", "integer"])
+                },
+            ],
+            constraint: None,
+        },
+        Table {
+            placeholder: "{meta}",
+            suffix: "_meta",
+            columns: &[
+                column("key", "TEXT PRIMARY KEY NOT NULL", "TEXT PRIMARY KEY", &["text"]),
+                column("value", "TEXT NOT NULL", "TEXT NOT NULL", &["text"]),
+            ],
+            constraint: None,
+        },
+        Table {
+            placeholder: "{sessions}",
+            suffix: "_sessions",
+            columns: &[
+                column("id", "INTEGER PRIMARY KEY NOT NULL", "BIGSERIAL PRIMARY KEY", &["bigint"]),
+                column(
+                    "todo_id",
+                    "INTEGER NOT NULL REFERENCES {todos} (id) ON DELETE CASCADE",
+                    "BIGINT NOT NULL REFERENCES {todos} (id) ON DELETE CASCADE",
+                    &["bigint"],
+                ),
+                column("started_at", "TIMESTAMP NOT NULL", "TIMESTAMPTZ NOT NULL", &["timestamp with time zone"]),
+                column("ended_at", "TIMESTAMP", "TIMESTAMPTZ", &["timestamp with time zone"]),
+            ],
+            constraint: None,
+        },
+        Table {
+            placeholder: "{deps}",
+            suffix: "_deps",
+            columns: &[
+                column(
+                    "todo_id",
+                    "INTEGER NOT NULL REFERENCES {todos} (id) ON DELETE CASCADE",
+                    "BIGINT NOT NULL REFERENCES {todos} (id) ON DELETE CASCADE",
+                    &["bigint"],
+                ),
+                column(
+                    "depends_on",
+                    "INTEGER NOT NULL REFERENCES {todos} (id) ON DELETE CASCADE",
+                    "BIGINT NOT NULL REFERENCES {todos} (id) ON DELETE CASCADE",
+                    &["bigint"],
+                ),
+            ],
+            constraint: Some("PRIMARY KEY (todo_id, depends_on)"),
+        },
+    ],
+    indexes: &[Index {
+        placeholder: "{order_index}",
+        suffix: "_order",
+        sqlite: LISTING_ORDER,
+        postgres: "(COALESCE(position, id)), created_at, id",
+    }],
+};
+
+/// what create_table makes
+pub fn expected() -> &'static Schema {
+    &SCHEMA
+}
+
+impl Schema {
+    pub fn todos(&self) -> &Table {
+        &self.tables[0]
+    }
+
+    /// (kind, name) of every table and index for the todos table named `table`
+    pub fn objects(&self, table: &str) -> Vec<(&'static str, String)> {
+        let tables = self.tables.iter().map(|t| ("table", format!("{table}{}", t.suffix)));
+        tables.chain(self.indexes.iter().map(|index| ("index", format!("{table}{}", index.suffix)))).collect()
+    }
+
+    /// the names of objects(), as the catalog of the database has them
+    pub fn object_names(&self, table: &str) -> Vec<String> {
+        self.objects(table).into_iter().map(|(_, name)| name).collect()
+    }
+}
+
+impl Column {
+    pub fn definition(&self, dialect: Dialect) -> &'static str {
+        match dialect {
+            Dialect::Sqlite => self.sqlite,
+            Dialect::Postgres => self.postgres,
+        }
+    }
+}
+
+impl Table {
+    /// CREATE TABLE IF NOT EXISTS with the placeholders left for TableName::sql
+    pub fn create(&self, dialect: Dialect) -> String {
+        let columns = self.columns.iter().map(|column| format!("{} {}", column.name, column.definition(dialect)));
+        let lines: Vec<String> = columns.chain(self.constraint.map(String::from)).collect();
+        format!("CREATE TABLE IF NOT EXISTS {} (\n    {}\n)", self.placeholder, lines.join(",\n    "))
+    }
+
+    pub fn column_names(&self) -> impl Iterator<Item = &'static str> {
+        self.columns.iter().map(|column| column.name)
+    }
+}
+
+impl Index {
+    /// CREATE INDEX IF NOT EXISTS on the todos table, the placeholders left for TableName::sql
+    pub fn create(&self, dialect: Dialect) -> String {
+        let expressions = match dialect {
+            Dialect::Sqlite => self.sqlite,
+            Dialect::Postgres => self.postgres,
+        };
+        format!("CREATE INDEX IF NOT EXISTS {} ON {{todos}} ({expressions})", self.placeholder)
+    }
+}
+
+/*-----------------------------------*/
+/*               tests               */
+/*-----------------------------------*/
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expected() {
+        let schema = expected();
+        assert_eq!(
+            schema.objects("todos"),
+            [
+                ("table", "todos"),
+                ("table", "todos_meta"),
+                ("table", "todos_sessions"),
+                ("table", "todos_deps"),
+                ("index", "todos_order"),
+            ]
+            .map(|(kind, name)| (kind, name.to_string()))
+        );
+        assert_eq!(
+            schema.tables[3].create(Dialect::Postgres),
+            "CREATE TABLE IF NOT EXISTS {deps} (\n    todo_id BIGINT NOT NULL REFERENCES {todos} (id) ON DELETE CASCADE,\n    \
+             depends_on BIGINT NOT NULL REFERENCES {todos} (id) ON DELETE CASCADE,\n    PRIMARY KEY (todo_id, depends_on)\n)"
+        );
+        assert_eq!(
+            schema.indexes[0].create(Dialect::Sqlite),
+            "CREATE INDEX IF NOT EXISTS {order_index} ON {todos} (COALESCE(position, id), created_at, id)"
+        );
+        // ADD COLUMN can't add a NOT NULL column without a default to a table with rows
+        for column in schema.todos().columns.iter().filter(|column| column.added) {
+            for dialect in [Dialect::Sqlite, Dialect::Postgres] {
+                let definition = column.definition(dialect);
+                assert!(!definition.contains("NOT NULL") || definition.contains("DEFAULT"), "{}", column.name);
+            }
+        }
+    }
+}
@@ -0,0 +1,233 @@
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+
+// what the errors list, so that every flag names the same formats
+const INSTANT_FORMATS: &str = "a date like 2024-05-01, a time like 2024-05-01T10:00:00Z, today, yesterday, tomorrow or a duration ago like 7d";
+const DURATION_FORMATS: &str = "a number followed by min, h, d, w or mo, like 90d";
+
+/// parse a point in time given on the command line, relative to `now`
+///
+/// dates are midnight UTC, times without an offset are UTC too,
+/// durations like `7d` are that long before now
+pub fn parse_instant(input: &str, now: DateTime<Utc>) -> anyhow::Result<DateTime<Utc>> {
+    let text = input.trim();
+    let midnight = |day: NaiveDate| day.and_time(NaiveTime::MIN).and_utc();
+
+    match text {
+        "today" => return Ok(midnight(now.date_naive())),
+        "yesterday" => return Ok(midnight(now.date_naive() - Duration::days(1))),
+        "tomorrow" => return Ok(midnight(now.date_naive() + Duration::days(1))),
+        _ => {}
+    }
+    if let Ok(day) = text.parse::<NaiveDate>() {
+        return Ok(midnight(day));
+    }
+    if let Ok(time) = DateTime::parse_from_rfc3339(text) {
+        return Ok(time.with_timezone(&Utc));
+    }
+    for format in ["%Y-%m-%dT%H:%M:%S", "%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M"] {
+        if let Ok(time) = NaiveDateTime::parse_from_str(text, format) {
+            return Ok(time.and_utc());
+        }
+    }
+    // a number and a unit was meant as a duration, its own error is the precise one
+    if split_duration(text).is_some() {
+        let ago = parse_duration(text)?;
+        // too far back to represent is as wrong as any other typo
+        return now
+            .checked_sub_signed(ago)
+            .ok_or_else(|| anyhow::anyhow!("{text} ago is too far back"));
+    }
+
+    Err(anyhow::anyhow!("Cannot parse time {text}, expected {INSTANT_FORMATS}"))
+}
+
+/// durations like `90d`, a number followed by min, h, d, w or mo where a month is 30 days
+pub fn parse_duration(input: &str) -> anyhow::Result<Duration> {
+    let text = input.trim();
+    let Some((amount, unit)) = split_duration(text) else {
+        return Err(anyhow::anyhow!("Cannot parse duration {text}, expected {DURATION_FORMATS}"));
+    };
+    let amount: i64 = amount
+        .parse()
+        .map_err(|_| anyhow::anyhow!("Duration {text} is too long"))?;
+
+    let duration = match unit {
+        "min" => Duration::try_minutes(amount),
+        "h" => Duration::try_hours(amount),
+        "d" => Duration::try_days(amount),
+        "w" => Duration::try_weeks(amount),
+        "mo" => amount.checked_mul(30).and_then(Duration::try_days),
+        "m" => {
+            return Err(anyhow::anyhow!(
+                "Duration {text} is ambiguous, write {amount}min for minutes or {amount}mo for months"
+            ))
+        }
+        _ => return Err(anyhow::anyhow!("Cannot parse duration {text}, expected {DURATION_FORMATS}")),
+    };
+    duration.ok_or_else(|| anyhow::anyhow!("Duration {text} is too long"))
+}
+
+/// minutes like 2d 4h or 1h 30min, a day is 24 hours like for parse_duration
+pub fn format_minutes(minutes: i64) -> String {
+    let parts: Vec<String> = [(minutes / (24 * 60), "d"), (minutes / 60 % 24, "h"), (minutes % 60, "min")]
+        .into_iter()
+        .filter(|(amount, _)| *amount > 0)
+        .map(|(amount, unit)| format!("{amount}{unit}"))
+        .collect();
+    if parts.is_empty() {
+        String::from("0min")
+    } else {
+        parts.join(" ")
+    }
+}
+
+/// the digits and the unit after them, None unless both are there
+fn split_duration(text: &str) -> Option<(&str, &str)> {
+    let digits = text.find(|c: char| !c.is_ascii_digit())?;
+    let (amount, unit) = text.split_at(digits);
+    (!amount.is_empty() && unit.chars().all(|c| c.is_ascii_lowercase())).then_some((amount, unit))
+}
+
+/*-----------------------------------*/
+/*               tests               */
+/*-----------------------------------*/
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(text: &str) -> DateTime<Utc> {
+        text.parse().unwrap()
+    }
+
+    #[test]
+    fn test_parse_instant() {
+        let now = at("2024-05-04T12:30:00Z");
+        let table = [
+            ("today", "2024-05-04T00:00:00Z"),
+            ("yesterday", "2024-05-03T00:00:00Z"),
+            ("tomorrow", "2024-05-05T00:00:00Z"),
+            (" today ", "2024-05-04T00:00:00Z"),
+            ("2024-05-01", "2024-05-01T00:00:00Z"),
+            ("2024-02-29", "2024-02-29T00:00:00Z"),
+            ("2024-05-01T10:00:00+02:00", "2024-05-01T08:00:00Z"),
+            ("2024-05-01T10:00:00Z", "2024-05-01T10:00:00Z"),
+            ("2024-05-01T10:00:00", "2024-05-01T10:00:00Z"),
+            ("2024-05-01T10:00", "2024-05-01T10:00:00Z"),
+            ("2024-05-01 10:00:30", "2024-05-01T10:00:30Z"),
+            ("2024-05-01 10:00", "2024-05-01T10:00:00Z"),
+            ("7d", "2024-04-27T12:30:00Z"),
+            ("2w", "2024-04-20T12:30:00Z"),
+            ("12h", "2024-05-04T00:30:00Z"),
+            ("30min", "2024-05-04T12:00:00Z"),
+            ("1mo", "2024-04-04T12:30:00Z"),
+            ("0d", "2024-05-04T12:30:00Z"),
+        ];
+        for (input, expected) in table {
+            assert_eq!(parse_instant(input, now).unwrap(), at(expected), "{input}");
+        }
+    }
+
+    #[test]
+    fn test_parse_instant_rejects() {
+        let now = at("2024-05-04T12:30:00Z");
+        let table = [
+            ("", "Cannot parse time , expected a date like 2024-05-01"),
+            ("soon", "Cannot parse time soon, expected"),
+            ("7", "Cannot parse time 7, expected"),
+            ("d", "Cannot parse time d, expected"),
+            ("-7d", "Cannot parse time -7d, expected"),
+            ("7 d", "Cannot parse time 7 d, expected"),
+            ("7D", "Cannot parse time 7D, expected"),
+            ("7ž", "Cannot parse time 7ž, expected"),
+            ("2024-13-01", "Cannot parse time 2024-13-01, expected"),
+            ("2023-02-29", "Cannot parse time 2023-02-29, expected"),
+            ("01/05/2024", "Cannot parse time 01/05/2024, expected"),
+            ("2024-05-01T25:00", "Cannot parse time 2024-05-01T25:00, expected"),
+            ("1m", "Duration 1m is ambiguous, write 1min for minutes or 1mo for months"),
+            ("7y", "Cannot parse duration 7y, expected a number followed by min, h, d, w or mo"),
+            ("99999999d", "99999999d ago is too far back"),
+            ("9999999999999999w", "Duration 9999999999999999w is too long"),
+            ("99999999999999999999d", "Duration 99999999999999999999d is too long"),
+        ];
+        for (input, expected) in table {
+            let err = parse_instant(input, now).unwrap_err().to_string();
+            assert!(err.starts_with(expected), "{input}: {err}");
+        }
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("90d").unwrap(), Duration::days(90));
+        assert_eq!(parse_duration("48h").unwrap(), Duration::hours(48));
+        assert_eq!(parse_duration("12w").unwrap(), Duration::weeks(12));
+        assert_eq!(parse_duration("45min").unwrap(), Duration::minutes(45));
+        assert_eq!(parse_duration("2mo").unwrap(), Duration::days(60));
+        assert_eq!(parse_duration("0h").unwrap(), Duration::zero());
+
+        for (input, expected) in [
+            ("3m", "Duration 3m is ambiguous, write 3min for minutes or 3mo for months"),
+            ("", "Cannot parse duration , expected a number followed by min, h, d, w or mo, like 90d"),
+            ("soon", "Cannot parse duration soon, expected"),
+            ("90", "Cannot parse duration 90, expected"),
+            ("-90d", "Cannot parse duration -90d, expected"),
+            ("1.5d", "Cannot parse duration 1.5d, expected"),
+            ("3mins", "Cannot parse duration 3mins, expected"),
+            ("2024-05-01", "Cannot parse duration 2024-05-01, expected"),
+            ("9999999999999999999mo", "Duration 9999999999999999999mo is too long"),
+        ] {
+            let err = parse_duration(input).unwrap_err().to_string();
+            assert!(err.starts_with(expected), "{input}: {err}");
+        }
+    }
+
+    #[test]
+    fn test_format_minutes() {
+        let table = [
+            (0, "0min"),
+            (1, "1min"),
+            (59, "59min"),
+            (60, "1h"),
+            (61, "1h 1min"),
+            (90, "1h 30min"),
+            (23 * 60 + 59, "23h 59min"),
+            (24 * 60, "1d"),
+            (24 * 60 + 1, "1d 1min"),
+            (2 * 24 * 60 + 4 * 60, "2d 4h"),
+            (3 * 24 * 60 + 5 * 60 + 7, "3d 5h 7min"),
+            (400 * 24 * 60, "400d"),
+        ];
+        for (minutes, expected) in table {
+            assert_eq!(format_minutes(minutes), expected, "{minutes}");
+        }
+        // whatever parses as a single unit comes back the same
+        for text in ["45min", "3h", "2d"] {
+            assert_eq!(format_minutes(parse_duration(text).unwrap().num_minutes()), text);
+        }
+    }
+
+    proptest::proptest! {
+        // whatever is typed after --since, it is an error at worst
+        #[test]
+        fn test_parse_instant_any_text(text in "\\PC*") {
+            let _ = parse_instant(&text, at("2024-05-04T12:30:00Z"));
+        }
+
+        #[test]
+        fn test_parse_duration_any_text(text in "\\PC*") {
+            let _ = parse_duration(&text);
+        }
+
+        // digits that look like a duration, with numbers far beyond what fits
+        #[test]
+        fn test_parse_instant_durations(text in "[0-9]{1,25}(min|h|d|w|mo|m|[a-z]{1,3})") {
+            let _ = parse_instant(&text, at("2024-05-04T12:30:00Z"));
+        }
+
+        // what a time is printed as parses back to the same time
+        #[test]
+        fn test_parse_instant_rfc3339(seconds in -8_000_000_000i64..250_000_000_000) {
+            let time = DateTime::from_timestamp(seconds, 0).unwrap();
+            proptest::prop_assert_eq!(parse_instant(&time.to_rfc3339(), at("2024-05-04T12:30:00Z")).unwrap(), time);
+        }
+    }
+}
@@ -0,0 +1,124 @@
+// the last change kept in the meta table for `todo undo`, there is no history beyond
+// it, so only a completion nothing else followed can be reverted
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::exit;
+use crate::ids::IdFormat;
+
+pub const META_LAST_CHANGE: &str = "last_change";
+
+// the last change to the todos, as far as undo cares
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum LastChange {
+    Completed { id: i64, at: DateTime<Utc> },
+    /// anything else, it can't be undone and hides the completion before it
+    Other { at: DateTime<Utc> },
+}
+
+impl LastChange {
+    /// None for a value written by another version, which is as good as no change
+    pub fn parse(value: &str) -> Option<Self> {
+        serde_json::from_str(value).ok()
+    }
+
+    pub fn to_meta(&self) -> String {
+        serde_json::to_string(self).expect("a change always serializes")
+    }
+}
+
+// what undo_completion did
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Undone {
+    Reopened,
+    /// reopened by other means since it was completed
+    NoLongerDone,
+    /// something else was recorded since the change undo was asked for
+    Changed,
+}
+
+/// the error for what kept undo_completion from reopening the todo
+pub fn undone(undone: Undone, id: i64, ids: IdFormat) -> anyhow::Result<()> {
+    match undone {
+        Undone::Reopened => Ok(()),
+        Undone::NoLongerDone => {
+            Err(exit::Failure::Conflict.error(format!("Nothing to undo, todo {} is no longer done", ids.show(id))))
+        }
+        Undone::Changed => Err(changed()),
+    }
+}
+
+fn changed() -> anyhow::Error {
+    exit::Failure::Conflict.error("Nothing to undo, something else changed since the last todo was completed")
+}
+
+/// the todo undo reopens at `now`, the error says why there is none
+pub fn undoable(last: Option<&LastChange>, window_secs: u64, now: DateTime<Utc>, ids: IdFormat) -> anyhow::Result<i64> {
+    match last {
+        None => Err(exit::Failure::NotFound.error("Nothing to undo, no completion was recorded")),
+        Some(LastChange::Other { .. }) => Err(changed()),
+        Some(LastChange::Completed { id, at }) => {
+            // a clock that went back a little still counts as just now
+            let ago = (now - *at).num_seconds().max(0);
+            if ago > i64::try_from(window_secs).unwrap_or(i64::MAX) {
+                return Err(exit::Failure::Conflict.error(format!(
+                    "Todo {} was completed {ago}s ago, undo only reverts completions within undo_window_secs ({window_secs}s)",
+                    ids.show(*id)
+                )));
+            }
+            Ok(*id)
+        }
+    }
+}
+
+/*-----------------------------------*/
+/*               tests               */
+/*-----------------------------------*/
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(text: &str) -> DateTime<Utc> {
+        text.parse().unwrap()
+    }
+
+    #[test]
+    fn test_undoable() {
+        let completed = LastChange::Completed {
+            id: 7,
+            at: at("2024-05-04T12:00:00Z"),
+        };
+        let ids = IdFormat::default();
+        let undo = |last: Option<&LastChange>, now| undoable(last, 30, at(now), ids).map_err(|err| err.to_string());
+
+        assert_eq!(undo(Some(&completed), "2024-05-04T12:00:00Z"), Ok(7));
+        assert_eq!(undo(Some(&completed), "2024-05-04T12:00:30Z"), Ok(7));
+        assert_eq!(undo(Some(&completed), "2024-05-04T11:59:58Z"), Ok(7));
+        assert_eq!(
+            undo(Some(&completed), "2024-05-04T12:00:31Z"),
+            Err(String::from(
+                "Todo 7 was completed 31s ago, undo only reverts completions within undo_window_secs (30s)"
+            ))
+        );
+        let edited = LastChange::Other {
+            at: at("2024-05-04T12:00:10Z"),
+        };
+        assert!(undo(Some(&edited), "2024-05-04T12:00:11Z").unwrap_err().contains("something else changed"));
+        assert!(undo(None, "2024-05-04T12:00:00Z").unwrap_err().starts_with("Nothing to undo"));
+        // a huge window never overflows
+        assert_eq!(undoable(Some(&completed), u64::MAX, at("2030-01-01T00:00:00Z"), ids).unwrap(), 7);
+    }
+
+    #[test]
+    fn test_meta_value() {
+        let completed = LastChange::Completed {
+            id: 7,
+            at: at("2024-05-04T12:00:00Z"),
+        };
+        assert_eq!(completed.to_meta(), r#"{"kind":"completed","id":7,"at":"2024-05-04T12:00:00Z"}"#);
+        assert_eq!(LastChange::parse(&completed.to_meta()), Some(completed));
+        assert_eq!(LastChange::parse("{\"kind\":\"renamed\"}"), None);
+        assert_eq!(LastChange::parse(""), None);
+    }
+}
@@ -0,0 +1,167 @@
+use std::collections::BTreeMap;
+use std::io::Write;
+
+use crate::ids::IdFormat;
+use crate::render::{self, Charset};
+use crate::{DBTrait, Todo, TodoFilter};
+
+// what happened to a todo between two looks at the table
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change {
+    Added(Todo),
+    Completed(Todo),
+    Reopened(Todo),
+    /// the description is another one now
+    Edited(Todo),
+    /// the todo as it was last seen
+    Removed(Todo),
+}
+
+/// the changes from `before` to `after`, in id order, a todo completed and edited
+/// at once is reported as completed
+pub fn diff(before: &[Todo], after: &[Todo]) -> Vec<Change> {
+    let before: BTreeMap<i64, &Todo> = before.iter().map(|todo| (todo.id, todo)).collect();
+    let after: BTreeMap<i64, &Todo> = after.iter().map(|todo| (todo.id, todo)).collect();
+    let mut ids: Vec<i64> = before.keys().chain(after.keys()).copied().collect();
+    ids.sort_unstable();
+    ids.dedup();
+
+    ids.into_iter()
+        .filter_map(|id| match (before.get(&id), after.get(&id)) {
+            (None, Some(todo)) => Some(Change::Added((*todo).clone())),
+            (Some(todo), None) => Some(Change::Removed((*todo).clone())),
+            (Some(old), Some(new)) if !old.done && new.done => Some(Change::Completed((*new).clone())),
+            (Some(old), Some(new)) if old.done && !new.done => Some(Change::Reopened((*new).clone())),
+            (Some(old), Some(new)) if old.description != new.description => Some(Change::Edited((*new).clone())),
+            _ => None,
+        })
+        .collect()
+}
+
+/// postgres channel the changes to a table are announced on, a hash because the schema
+/// and table names together may be longer than a channel name can be
+pub fn channel(table: &str) -> String {
+    // FNV-1a like config::fingerprint
+    let hash = table
+        .bytes()
+        .fold(0xcbf29ce484222325u64, |hash, byte| (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3));
+    format!("todo_changes_{hash:016x}")
+}
+
+/// print the changes to the todos every `interval`, or as soon as postgres announces one,
+/// until Ctrl-C, or only the changes over one interval when `once` is set
+pub async fn watch(
+    database: &impl DBTrait,
+    interval: std::time::Duration,
+    once: bool,
+    ids: IdFormat,
+    charset: Charset,
+    mut output: impl Write,
+) -> anyhow::Result<()> {
+    // a listener that fails, like behind a pooler that can't LISTEN, leaves polling
+    let mut listener = database.change_listener().await.unwrap_or(None);
+    match listener {
+        Some(_) => eprintln!(
+            "Watching for changes announced by postgres, and every {}s for the others",
+            interval.as_secs()
+        ),
+        None => eprintln!("Watching for changes every {}s", interval.as_secs()),
+    }
+
+    let mut seen = database.list_todos(&TodoFilter::default()).await?;
+    loop {
+        let changed = async {
+            match listener.as_mut() {
+                Some(listener) => listener.changed().await,
+                None => std::future::pending().await,
+            }
+        };
+        let lost = tokio::select! {
+            _ = tokio::signal::ctrl_c() => return Ok(()),
+            _ = tokio::time::sleep(interval) => false,
+            result = changed => result.is_err(),
+        };
+        if lost {
+            eprintln!("Lost the postgres notifications, watching every {}s", interval.as_secs());
+            listener = None;
+        }
+
+        let current = database.list_todos(&TodoFilter::default()).await?;
+        for change in diff(&seen, &current) {
+            writeln!(output, "{}", render::change_line(&change, ids, charset))?;
+        }
+        output.flush()?;
+        seen = current;
+        if once {
+            return Ok(());
+        }
+    }
+}
+
+/*-----------------------------------*/
+/*               tests               */
+/*-----------------------------------*/
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn todo(id: i64, description: &str, done: bool) -> Todo {
+        Todo {
+            id,
+            description: description.to_string(),
+            done,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_diff() {
+        let before = [
+            todo(3, "call the bank", false),
+            todo(5, "write report", false),
+            todo(9, "send invoice", false),
+            todo(11, "water plants", true),
+            todo(12, "unchanged", false),
+        ];
+        let after = [
+            todo(5, "write the report", false),
+            todo(9, "send invoice", true),
+            todo(11, "water plants", false),
+            todo(12, "unchanged", false),
+            todo(14, "buy milk", false),
+        ];
+        assert_eq!(
+            diff(&before, &after),
+            vec![
+                Change::Removed(todo(3, "call the bank", false)),
+                Change::Edited(todo(5, "write the report", false)),
+                Change::Completed(todo(9, "send invoice", true)),
+                Change::Reopened(todo(11, "water plants", false)),
+                Change::Added(todo(14, "buy milk", false)),
+            ]
+        );
+        assert!(diff(&before, &before).is_empty());
+        assert!(diff(&[], &[]).is_empty());
+        // completed and renamed in the same tick
+        assert_eq!(
+            diff(&[todo(1, "a", false)], &[todo(1, "b", true)]),
+            vec![Change::Completed(todo(1, "b", true))]
+        );
+        // pinning or moving isn't worth a line
+        let pinned = Todo {
+            pinned: true,
+            ..todo(1, "a", false)
+        };
+        assert!(diff(&[todo(1, "a", false)], &[pinned]).is_empty());
+    }
+
+    #[test]
+    fn test_channel() {
+        let channel = channel("\"todos\"");
+        assert_eq!(channel.len(), "todo_changes_".len() + 16);
+        assert_eq!(super::channel("\"todos\""), channel);
+        assert_ne!(super::channel("\"team\".\"todos\""), channel);
+        // a channel is an identifier, postgres cuts them at 63 bytes
+        assert!(super::channel(&"x".repeat(200)).len() < 64);
+    }
+}
@@ -0,0 +1,175 @@
+// snapshots of everything the binary prints, scripts parse it, so a changed message or format
+// has to be accepted on purpose with `cargo insta review`
+use assert_cmd::Command;
+use regex::Regex;
+use std::path::Path;
+
+/// the binary working on the database at `path` only, like in tests/cli.rs
+fn todo(path: &Path) -> Command {
+    let mut command = Command::cargo_bin("db_test").unwrap();
+    command
+        .arg("--database-url")
+        .arg(path)
+        .env("TODO_CONFIG", path.with_extension("toml"))
+        .env("XDG_CACHE_HOME", path.with_extension("cache"))
+        .env_remove("TODO_PROFILE")
+        .env("LC_ALL", "C.UTF-8")
+        .env_remove("TODO_PASSPHRASE")
+        .env_remove("PGPASSWORD")
+        // a backtrace would be part of every error
+        .env("RUST_BACKTRACE", "0")
+        .env("RUST_LIB_BACKTRACE", "0");
+    command
+}
+
+/// the parts of the output that differ from run to run: timestamps and dates, measured or
+/// configured durations, also those json and toml give as a number, and absolute paths like that of the temporary database
+fn redact(output: &str) -> String {
+    let timestamps = Regex::new(r"\d{4}-\d{2}-\d{2}([T ]\d{2}:\d{2}(:\d{2}(\.\d+)?)?(Z|[+-]\d{2}:?\d{2})?)?").unwrap();
+    // 1h 30min is one duration, not two
+    let durations = Regex::new(r"\b\d+(\.\d+)? ?(ms|s|min|m|h|d)\b( \d+(\.\d+)? ?(ms|s|min|m|h|d)\b)*").unwrap();
+    // json and toml have them as a number of the unit in the name
+    let fields = Regex::new(r#"(_(micros|secs)"? ?[:=] ?)\d+"#).unwrap();
+    let paths = Regex::new(r#"(^|[\s"'=(])/[^\s"')]+"#).unwrap();
+
+    let output = timestamps.replace_all(output, "<TS>");
+    let output = durations.replace_all(&output, "<DUR>");
+    let output = fields.replace_all(&output, "${1}<DUR>");
+    paths.replace_all(&output, "${1}<PATH>").into_owned()
+}
+
+/// every command with what it printed, redacted, and its exit code, one after the other
+fn transcript(path: &Path, format: &str, commands: &[&[&str]]) -> String {
+    let mut transcript = String::new();
+    for command in commands {
+        let output = todo(path).args(["--format", format]).args(*command).write_stdin("").output().unwrap();
+        // quoted like in a shell where spaces would split them
+        let words: Vec<String> = command
+            .iter()
+            .map(|word| if word.contains(' ') { format!("'{word}'") } else { word.to_string() })
+            .collect();
+        transcript.push_str(&format!("$ todo {}\n", words.join(" ")));
+        transcript.push_str(&redact(&String::from_utf8_lossy(&output.stdout)));
+        for line in redact(&String::from_utf8_lossy(&output.stderr)).lines() {
+            transcript.push_str(&format!("stderr: {line}\n"));
+        }
+        transcript.push_str(&format!("exit: {}\n\n", output.status.code().unwrap()));
+    }
+    transcript
+}
+
+/// an empty sqlite file that may undo completions for five minutes, in a directory removed
+/// when the returned guard is dropped
+fn database() -> (tempfile::TempDir, std::path::PathBuf) {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("todos.db");
+    std::fs::File::create(&path).unwrap();
+    std::fs::write(path.with_extension("toml"), "undo_window_secs = 300\n").unwrap();
+    (dir, path)
+}
+
+// the same session in every format, every command once and the empty table at the end
+const SESSION: &[&[&str]] = &[
+    &["add", "buy milk"],
+    &["add", "send invoice"],
+    &["add", "deploy", "--after", "1"],
+    &["add", "call the bank"],
+    &["list"],
+    &["list", "--pending", "--with-time"],
+    &["list", "--group-by", "status"],
+    &["list", "--stats-only"],
+    &["show", "1"],
+    &["pin", "2"],
+    &["list", "--pinned"],
+    &["unpin", "2"],
+    &["estimate", "2", "90min"],
+    &["edit", "2", "--append", " (waiting on Bob)"],
+    &["edit", "2", "--prepend", "[URGENT] "],
+    &["edit", "3", "deploy to staging"],
+    &["start", "1"],
+    &["stop"],
+    &["done", "1"],
+    &["undo"],
+    &["done", "1"],
+    &["done", "1"],
+    &["stats"],
+    &["stats", "--by-day", "--days", "3"],
+    &["streak"],
+    &["prompt"],
+    &["merge", "4", "2"],
+    &["move", "2", "--top"],
+    &["purge", "--older-than", "90d", "--dry-run"],
+    &["tenants"],
+    &["db", "doctor"],
+    &["clear"],
+    &["clear", "--yes"],
+    &["list"],
+    &["stats"],
+    &["prompt"],
+];
+
+#[test]
+fn test_plain() {
+    let (_dir, path) = database();
+    insta::assert_snapshot!(transcript(&path, "plain", SESSION));
+}
+
+#[test]
+fn test_json() {
+    let (_dir, path) = database();
+    insta::assert_snapshot!(transcript(&path, "json", SESSION));
+}
+
+#[test]
+fn test_toml() {
+    let (_dir, path) = database();
+    insta::assert_snapshot!(transcript(&path, "toml", SESSION));
+}
+
+#[test]
+fn test_errors() {
+    let (_dir, path) = database();
+    let mut errors = transcript(
+        &path,
+        "plain",
+        &[
+            &["done", "abc"],
+            &["done", "--strict", "9"],
+            &["show", "9"],
+            &["add", "deploy", "--after", "9"],
+            &["list", "--since", "soon"],
+            &["bogus"],
+        ],
+    );
+    // a database that can't be opened
+    errors.push_str(&transcript(&path.with_file_name("missing").join("todos.db"), "plain", &[&["list"]]));
+    insta::assert_snapshot!(errors);
+}
+
+#[test]
+fn test_redact() {
+    let table = [
+        ("created 2024-05-01T10:00:00Z", "created <TS>"),
+        ("at 2024-05-01T10:00:00.123+02:00.", "at <TS>."),
+        ("2024-05-01 10:00 and 2024-05-02", "<TS> and <TS>"),
+        ("2024-05-01 3", "<TS> 3"),
+        ("ping: 0.7 ms", "ping: <DUR>"),
+        ("Tracked: 0min", "Tracked: <DUR>"),
+        ("estimated at 1h 30min", "estimated at <DUR>"),
+        ("within 300s to reopen todo 1", "within <DUR> to reopen todo 1"),
+        ("{\"ping_micros\":303,\"tracked_secs\":[0,5]}", "{\"ping_micros\":<DUR>,\"tracked_secs\":[0,5]}"),
+        ("ping_micros = 1200", "ping_micros = <DUR>"),
+        ("url: /tmp/.tmpAb12/todos.db", "url: <PATH>"),
+        ("\"path\":\"/tmp/x.db\"", "\"path\":\"<PATH>\""),
+        ("/var/lib/todos.db", "<PATH>"),
+        // what only looks similar stays
+        ("Current streak: 1 days", "Current streak: 1 days"),
+        ("todo 12 of 30", "todo 12 of 30"),
+        ("2/3 done 66%", "2/3 done 66%"),
+        ("size: 32768 bytes", "size: 32768 bytes"),
+        ("3mins", "3mins"),
+    ];
+    for (input, expected) in table {
+        assert_eq!(redact(input), expected, "{input}");
+    }
+}